@@ -0,0 +1,461 @@
+use goose::goose::{GooseResponse, GooseUser, TransactionError, TransactionResult};
+use reqwest_12::{Response, header::CONTENT_TYPE};
+use serde_json::Value;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// Expectations on the response of a transaction.
+///
+/// Responses not meeting them are reported to goose as failed requests, with the assertion as error.
+#[derive(Clone, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Assertions {
+    /// The expected status code. Defaults to any successful status code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// The expected media type of the response, e.g. `application/json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// The minimum number of items of a list response, either a JSON array or an object with an `items` array
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+
+    /// Checks of values in a JSON response, e.g. `/total > 0`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub json: Vec<JsonCheck>,
+}
+
+impl Assertions {
+    /// Expect a JSON response
+    pub fn json() -> Self {
+        Self {
+            content_type: Some("application/json".into()),
+            ..Default::default()
+        }
+    }
+
+    /// Expect a JSON response with a `total` field
+    pub fn list() -> Self {
+        Self::json().check(JsonCheck::exists("/total"))
+    }
+
+    pub fn min_items(mut self, min_items: usize) -> Self {
+        self.min_items = Some(min_items);
+        self
+    }
+
+    pub fn check(mut self, check: JsonCheck) -> Self {
+        self.json.push(check);
+        self
+    }
+
+    fn needs_body(&self) -> bool {
+        self.min_items.is_some() || !self.json.is_empty()
+    }
+
+    /// Check a response, returning the first violated assertion.
+    fn verify(&self, status: u16, content_type: Option<&str>, body: &str) -> Result<(), String> {
+        match self.status {
+            Some(expected) if status != expected => {
+                return Err(format!("expected status {expected}, got {status}"));
+            }
+            None if !(200..300).contains(&status) => {
+                return Err(format!("expected a successful status, got {status}"));
+            }
+            _ => {}
+        }
+
+        if let Some(expected) = &self.content_type {
+            let actual = content_type
+                .and_then(|value| value.split(';').next())
+                .map(str::trim)
+                .unwrap_or_default();
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(format!("expected content type {expected}, got '{actual}'"));
+            }
+        }
+
+        if !self.needs_body() {
+            return Ok(());
+        }
+
+        let body: Value =
+            serde_json::from_str(body).map_err(|err| format!("invalid JSON response: {err}"))?;
+
+        if let Some(min_items) = self.min_items {
+            let items = match &body {
+                Value::Array(items) => Some(items),
+                body => body.get("items").and_then(Value::as_array),
+            };
+            let Some(items) = items else {
+                return Err("expected a list of items".into());
+            };
+            if items.len() < min_items {
+                return Err(format!(
+                    "expected at least {min_items} items, got {}",
+                    items.len()
+                ));
+            }
+        }
+
+        self.json.iter().try_for_each(|check| check.verify(&body))
+    }
+}
+
+/// Check the response of a request, marking the request as failed if an assertion doesn't hold.
+///
+/// Failed assertions and failed requests (e.g. timeouts or connection errors) fail the transaction.
+pub async fn verify(
+    user: &GooseUser,
+    response: GooseResponse,
+    assertions: &Assertions,
+) -> TransactionResult {
+    check(user, response, assertions, assertions.needs_body())
+        .await
+        .map(|_| ())
+}
+
+/// Check the response of a request like [`verify`], returning the body of the response.
+pub async fn verify_body(
+    user: &GooseUser,
+    response: GooseResponse,
+    assertions: &Assertions,
+) -> Result<String, Box<TransactionError>> {
+    check(user, response, assertions, true).await
+}
+
+async fn check(
+    user: &GooseUser,
+    response: GooseResponse,
+    assertions: &Assertions,
+    read_body: bool,
+) -> Result<String, Box<TransactionError>> {
+    let GooseResponse {
+        mut request,
+        response,
+    } = response;
+    let response = received(response)?;
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+
+    let body = match read_body {
+        true => match response.text().await {
+            Ok(body) => body,
+            Err(err) => {
+                return user
+                    .set_failure(
+                        &format!("failed to read response: {err}"),
+                        &mut request,
+                        Some(&headers),
+                        None,
+                    )
+                    .map(|()| String::new());
+            }
+        },
+        false => String::new(),
+    };
+
+    match assertions.verify(status.as_u16(), content_type, &body) {
+        Ok(()) if assertions.status.is_some() => {
+            // an explicitly expected status counts as success, even if it's an error status
+            user.set_success(&mut request).map(|()| body)
+        }
+        Ok(()) => Ok(body),
+        // goose fails the transaction, besides reporting the failed request
+        Err(err) => user
+            .set_failure(
+                &format!("assertion failed: {err}"),
+                &mut request,
+                Some(&headers),
+                Some(&body),
+            )
+            .map(|()| body),
+    }
+}
+
+/// The response of a request, failing the transaction if there is none, e.g. because of a timeout.
+///
+/// The failed request itself has already been reported by goose.
+fn received(
+    response: Result<Response, reqwest_12::Error>,
+) -> Result<Response, Box<TransactionError>> {
+    response.map_err(|err| Box::new(TransactionError::Reqwest(err)))
+}
+
+/// The comparison of a [`JsonCheck`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    const ALL: [(&'static str, Op); 6] = [
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+    ];
+
+    fn as_str(&self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(_, op)| op == self)
+            .map(|(s, _)| *s)
+            .unwrap_or_default()
+    }
+}
+
+/// A check of a value in a JSON response, written as `<pointer> [<op> <JSON value>]`.
+///
+/// Without a comparison, the value must exist and not be `null`, e.g. `/items/0/uuid`. Otherwise the value is
+/// compared using one of `==`, `!=`, `>`, `>=`, `<`, `<=`, e.g. `/total > 0` or `/status == "ok"`. Ordering
+/// comparisons require numbers.
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct JsonCheck {
+    pointer: String,
+    comparison: Option<(Op, Value)>,
+}
+
+impl JsonCheck {
+    pub fn exists(pointer: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            comparison: None,
+        }
+    }
+
+    pub fn compare(pointer: impl Into<String>, op: Op, value: impl Into<Value>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            comparison: Some((op, value.into())),
+        }
+    }
+
+    fn verify(&self, body: &Value) -> Result<(), String> {
+        let actual = body.pointer(&self.pointer).filter(|value| !value.is_null());
+        let Some(actual) = actual else {
+            return Err(format!("{} is missing", self.pointer));
+        };
+        let Some((op, expected)) = &self.comparison else {
+            return Ok(());
+        };
+
+        let ordering = match (actual.as_f64(), expected.as_f64()) {
+            (Some(actual), Some(expected)) => actual.partial_cmp(&expected),
+            _ => None,
+        };
+        let ok = match (op, ordering) {
+            (Op::Eq, Some(ordering)) => ordering == Ordering::Equal,
+            (Op::Ne, Some(ordering)) => ordering != Ordering::Equal,
+            (Op::Eq, None) => actual == expected,
+            (Op::Ne, None) => actual != expected,
+            (Op::Gt, Some(ordering)) => ordering == Ordering::Greater,
+            (Op::Ge, Some(ordering)) => ordering != Ordering::Less,
+            (Op::Lt, Some(ordering)) => ordering == Ordering::Less,
+            (Op::Le, Some(ordering)) => ordering != Ordering::Greater,
+            (Op::Gt | Op::Ge | Op::Lt | Op::Le, None) => false,
+        };
+
+        match ok {
+            true => Ok(()),
+            false => Err(format!("expected {self}, got {actual}")),
+        }
+    }
+}
+
+impl Display for JsonCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.comparison {
+            Some((op, value)) => write!(f, "{} {} {value}", self.pointer, op.as_str()),
+            None => f.write_str(&self.pointer),
+        }
+    }
+}
+
+impl FromStr for JsonCheck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (pointer, rest) = s.split_at(
+            s.find(|c: char| c.is_whitespace() || "=!<>".contains(c))
+                .unwrap_or(s.len()),
+        );
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(format!("JSON pointer must start with '/': {pointer}"));
+        }
+
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Ok(Self::exists(pointer));
+        }
+
+        let (op, value) = Op::ALL
+            .iter()
+            .find_map(|(token, op)| rest.strip_prefix(token).map(|value| (*op, value)))
+            .ok_or_else(|| format!("unknown comparison: {rest}"))?;
+        let value: Value = serde_json::from_str(value.trim())
+            .map_err(|err| format!("invalid JSON value '{}': {err}", value.trim()))?;
+
+        Ok(Self::compare(pointer, op, value))
+    }
+}
+
+impl TryFrom<String> for JsonCheck {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<JsonCheck> for String {
+    fn from(value: JsonCheck) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn check(s: &str) -> JsonCheck {
+        s.parse().expect("must parse")
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(check("/total > 0"), JsonCheck::compare("/total", Op::Gt, 0));
+        assert_eq!(
+            check("/total>=10"),
+            JsonCheck::compare("/total", Op::Ge, 10)
+        );
+        assert_eq!(
+            check(r#"/status == "ok""#),
+            JsonCheck::compare("/status", Op::Eq, "ok")
+        );
+        assert_eq!(check("/items/0/uuid"), JsonCheck::exists("/items/0/uuid"));
+        "total > 0".parse::<JsonCheck>().expect_err("must fail");
+        "/total ~ 0".parse::<JsonCheck>().expect_err("must fail");
+        "/status == ok".parse::<JsonCheck>().expect_err("must fail");
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for s in ["/total > 0", r#"/status != "ok""#, "/items/0/uuid"] {
+            assert_eq!(check(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn compare() {
+        let body = json!({"total": 3, "status": "ok", "score": 7.5, "none": null});
+        assert!(check("/total > 0").verify(&body).is_ok());
+        assert!(check("/total == 3.0").verify(&body).is_ok());
+        assert!(check("/score <= 7.5").verify(&body).is_ok());
+        assert!(check(r#"/status == "ok""#).verify(&body).is_ok());
+        assert!(check("/total < 3").verify(&body).is_err());
+        assert!(check(r#"/status > "a""#).verify(&body).is_err());
+        assert!(check("/none").verify(&body).is_err());
+        assert!(check("/missing").verify(&body).is_err());
+    }
+
+    #[test]
+    fn content_type() {
+        let assertions = Assertions::json();
+        assert!(
+            assertions
+                .verify(200, Some("application/json; charset=utf-8"), "")
+                .is_ok()
+        );
+        assert!(assertions.verify(200, Some("text/html"), "").is_err());
+        assert!(assertions.verify(200, None, "").is_err());
+    }
+
+    #[test]
+    fn status() {
+        let assertions = Assertions {
+            status: Some(204),
+            ..Default::default()
+        };
+        assert!(assertions.verify(204, None, "").is_ok());
+        assert!(assertions.verify(200, None, "").is_err());
+    }
+
+    #[test]
+    fn error_status() {
+        let body = r#"{"error": "Internal Server Error"}"#;
+        assert!(
+            Assertions::json()
+                .verify(500, Some("application/json"), body)
+                .is_err()
+        );
+        assert!(Assertions::default().verify(404, None, "").is_err());
+        assert!(Assertions::default().verify(204, None, "").is_ok());
+        // an explicitly expected error status
+        let assertions = Assertions {
+            status: Some(404),
+            ..Default::default()
+        };
+        assert!(assertions.verify(404, None, "").is_ok());
+    }
+
+    #[test]
+    fn request_error() {
+        let err = reqwest_12::Client::new()
+            .get("not a URL")
+            .build()
+            .expect_err("must fail");
+        let err = received(Err(err)).expect_err("must fail the transaction");
+        assert!(matches!(*err, TransactionError::Reqwest(_)));
+    }
+
+    #[test]
+    fn min_items() {
+        let assertions = Assertions::default().min_items(1);
+        assert!(assertions.verify(200, None, r#"{"items": [1]}"#).is_ok());
+        assert!(assertions.verify(200, None, "[1, 2]").is_ok());
+        assert!(
+            assertions
+                .verify(200, None, r#"{"items": [], "total": 0}"#)
+                .is_err()
+        );
+        assert!(assertions.verify(200, None, "{}").is_err());
+        assert!(assertions.verify(200, None, "<html></html>").is_err());
+    }
+
+    #[test]
+    fn deserialize() {
+        let assertions: Assertions = serde_json5::from_str(
+            r#"{ status: 200, content_type: "application/json", min_items: 1, json: ["/total > 0"] }"#,
+        )
+        .expect("must parse");
+        assert_eq!(
+            assertions,
+            Assertions {
+                status: Some(200),
+                ..Assertions::json()
+                    .min_items(1)
+                    .check(JsonCheck::compare("/total", Op::Gt, 0))
+            }
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use crate::{
+    monitor::StatusSample, profile::StageResult, server_metrics::Series, statements::Statement,
+    stats::TransactionStats,
+};
+use anyhow::Context;
+use std::{
+    fmt::{self, Display, Formatter},
+    io::BufReader,
+};
+
+/// The machine-readable result of a run, which can be used as baseline of a later run.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct RunResult {
+    /// The duration of the run, in seconds
+    pub duration: usize,
+    pub transactions: Vec<TransactionSummary>,
+    /// The latencies of the transactions of paced users, measured from their scheduled starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub latency: Vec<TransactionSummary>,
+    /// The transactions of the warm-up, which are not part of the `transactions`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<TransactionSummary>,
+    /// The status of the analysis graph cache, sampled during the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub analysis_status: Vec<StatusSample>,
+    /// The stages of a load profile, the transactions being the ones of the last stage
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageResult>,
+    /// The metrics of the server, sampled during the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub server_metrics: Vec<Series>,
+    /// The top SQL statements of the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statements: Vec<Statement>,
+}
+
+/// The result of a single transaction, latencies in milliseconds.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct TransactionSummary {
+    pub name: String,
+    pub count: usize,
+    pub failed: usize,
+    pub mean: f64,
+    pub p50: usize,
+    pub p95: usize,
+    pub p99: usize,
+    /// Runs per second
+    pub throughput: f64,
+}
+
+impl TransactionSummary {
+    fn new(duration: usize, stats: &TransactionStats) -> Self {
+        Self {
+            name: stats.name.clone(),
+            count: stats.count,
+            failed: stats.failed,
+            mean: stats.mean(),
+            p50: stats.percentile(0.5),
+            p95: stats.percentile(0.95),
+            p99: stats.percentile(0.99),
+            throughput: match duration {
+                0 => 0.0,
+                duration => stats.count as f64 / duration as f64,
+            },
+        }
+    }
+}
+
+impl RunResult {
+    pub fn new(duration: usize, stats: &[TransactionStats]) -> Self {
+        Self {
+            duration,
+            transactions: summaries(duration, stats),
+            latency: vec![],
+            warmup: vec![],
+            analysis_status: vec![],
+            stages: vec![],
+            server_metrics: vec![],
+            statements: vec![],
+        }
+    }
+
+    pub fn load(file: &str) -> anyhow::Result<Self> {
+        serde_json::from_reader(BufReader::new(
+            std::fs::File::open(file).with_context(|| format!("opening result file: {file}"))?,
+        ))
+        .with_context(|| format!("reading result file: {file}"))
+    }
+
+    pub fn store(&self, file: &str) -> anyhow::Result<()> {
+        std::fs::write(file, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing result file: {file}"))
+    }
+
+    /// Compare the transactions of this run to the ones of a baseline run.
+    ///
+    /// The tolerance is the change in percent which is still considered unchanged.
+    pub fn compare(&self, baseline: &RunResult, tolerance: f64) -> Vec<Comparison> {
+        let mut result: Vec<_> = self
+            .transactions
+            .iter()
+            .map(|current| {
+                let baseline = baseline
+                    .transactions
+                    .iter()
+                    .find(|b| b.name == current.name);
+                Comparison::new(current.name.clone(), baseline, Some(current), tolerance)
+            })
+            .collect();
+
+        result.extend(
+            baseline
+                .transactions
+                .iter()
+                .filter(|b| !self.transactions.iter().any(|c| c.name == b.name))
+                .map(|baseline| {
+                    Comparison::new(baseline.name.clone(), Some(baseline), None, tolerance)
+                }),
+        );
+
+        result
+    }
+}
+
+/// Summarize the stats of a run, taking `duration` seconds.
+pub(crate) fn summaries(duration: usize, stats: &[TransactionStats]) -> Vec<TransactionSummary> {
+    stats
+        .iter()
+        .map(|stats| TransactionSummary::new(duration, stats))
+        .collect()
+}
+
+/// The outcome of comparing a transaction to its baseline
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Verdict {
+    Unchanged,
+    Regression,
+    Improvement,
+    /// Not part of the baseline
+    New,
+    /// Only part of the baseline
+    Missing,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unchanged => "unchanged",
+            Self::Regression => "REGRESSION",
+            Self::Improvement => "improvement",
+            Self::New => "new",
+            Self::Missing => "missing",
+        })
+    }
+}
+
+/// The comparison of a transaction to its baseline, with changes in percent.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Comparison {
+    pub name: String,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+    pub throughput: Option<f64>,
+    pub verdict: Verdict,
+}
+
+impl Comparison {
+    fn new(
+        name: String,
+        baseline: Option<&TransactionSummary>,
+        current: Option<&TransactionSummary>,
+        tolerance: f64,
+    ) -> Self {
+        let (baseline, current) = match (baseline, current) {
+            (Some(baseline), Some(current)) => (baseline, current),
+            (baseline, _) => {
+                return Self {
+                    name,
+                    p50: None,
+                    p95: None,
+                    throughput: None,
+                    verdict: match baseline {
+                        Some(_) => Verdict::Missing,
+                        None => Verdict::New,
+                    },
+                };
+            }
+        };
+
+        let p50 = change(baseline.p50 as f64, current.p50 as f64);
+        let p95 = change(baseline.p95 as f64, current.p95 as f64);
+        let throughput = change(baseline.throughput, current.throughput);
+
+        // latencies should go down, throughput should go up
+        let changes = [p50, p95, throughput.map(|throughput| -throughput)];
+        let verdict = if changes.iter().flatten().any(|change| *change > tolerance) {
+            Verdict::Regression
+        } else if changes.iter().flatten().any(|change| *change < -tolerance) {
+            Verdict::Improvement
+        } else {
+            Verdict::Unchanged
+        };
+
+        Self {
+            name,
+            p50,
+            p95,
+            throughput,
+            verdict,
+        }
+    }
+}
+
+/// The change from the baseline to the current value, in percent. `None` if there is no baseline value.
+fn change(baseline: f64, current: f64) -> Option<f64> {
+    (baseline > 0.0).then(|| (current - baseline) / baseline * 100.0)
+}
+
+/// Print the comparisons as a table
+pub(crate) fn print_table(comparisons: &[Comparison]) {
+    let width = comparisons
+        .iter()
+        .map(|c| c.name.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("TRANSACTION".len());
+
+    let format = |change: Option<f64>| {
+        change
+            .map(|change| format!("{change:+.1}%"))
+            .unwrap_or_else(|| "-".into())
+    };
+
+    println!(
+        "{:<width$}  {:>9}  {:>9}  {:>10}  RESULT",
+        "TRANSACTION", "P50", "P95", "THROUGHPUT"
+    );
+    for comparison in comparisons {
+        println!(
+            "{:<width$}  {:>9}  {:>9}  {:>10}  {}",
+            comparison.name,
+            format(comparison.p50),
+            format(comparison.p95),
+            format(comparison.throughput),
+            comparison.verdict,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tx(name: &str, p50: usize, p95: usize, throughput: f64) -> TransactionSummary {
+        TransactionSummary {
+            name: name.into(),
+            count: 100,
+            failed: 0,
+            mean: p50 as f64,
+            p50,
+            p95,
+            p99: p95,
+            throughput,
+        }
+    }
+
+    fn run(transactions: Vec<TransactionSummary>) -> RunResult {
+        RunResult {
+            duration: 60,
+            transactions,
+            latency: vec![],
+            warmup: vec![],
+            analysis_status: vec![],
+            stages: vec![],
+            server_metrics: vec![],
+            statements: vec![],
+        }
+    }
+
+    fn verdicts(comparisons: &[Comparison]) -> Vec<(&str, Verdict)> {
+        comparisons
+            .iter()
+            .map(|c| (c.name.as_str(), c.verdict))
+            .collect()
+    }
+
+    #[test]
+    fn new_from_stats() {
+        let stats = TransactionStats {
+            name: "get_sbom".into(),
+            times: [(10, 9), (100, 1)].into_iter().collect(),
+            total_time: 190,
+            count: 10,
+            failed: 1,
+        };
+        let result = RunResult::new(5, &[stats]);
+        assert_eq!(
+            result.transactions[0],
+            TransactionSummary {
+                name: "get_sbom".into(),
+                count: 10,
+                failed: 1,
+                mean: 19.0,
+                p50: 10,
+                p95: 100,
+                p99: 100,
+                throughput: 2.0,
+            }
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let result = run(vec![tx("get_sbom", 10, 20, 1.5)]);
+        let json = serde_json::to_string(&result).expect("must serialize");
+        assert_eq!(
+            serde_json::from_str::<RunResult>(&json).expect("must deserialize"),
+            result
+        );
+    }
+
+    #[test]
+    fn analysis_status() {
+        let mut result = run(vec![]);
+        result.analysis_status = vec![StatusSample {
+            elapsed: 10,
+            sbom_count: Some(12),
+            graph_count: Some(3),
+            loading: true,
+        }];
+        let json = serde_json::to_string(&result).expect("must serialize");
+        assert_eq!(
+            serde_json::from_str::<RunResult>(&json).expect("must deserialize"),
+            result
+        );
+
+        // results stored without monitoring the analysis status
+        let result: RunResult =
+            serde_json::from_str(r#"{"duration": 60, "transactions": []}"#).expect("must load");
+        assert!(result.analysis_status.is_empty());
+    }
+
+    #[test]
+    fn compare() {
+        let baseline = run(vec![
+            tx("same", 100, 200, 10.0),
+            tx("slower", 100, 200, 10.0),
+            tx("faster", 100, 200, 10.0),
+            tx("less_throughput", 100, 200, 10.0),
+            tx("gone", 100, 200, 10.0),
+        ]);
+        let current = run(vec![
+            tx("same", 105, 190, 10.5),
+            tx("slower", 100, 300, 10.0),
+            tx("faster", 50, 200, 10.0),
+            tx("less_throughput", 100, 200, 5.0),
+            tx("added", 100, 200, 10.0),
+        ]);
+
+        let comparisons = current.compare(&baseline, 10.0);
+        assert_eq!(
+            verdicts(&comparisons),
+            [
+                ("same", Verdict::Unchanged),
+                ("slower", Verdict::Regression),
+                ("faster", Verdict::Improvement),
+                ("less_throughput", Verdict::Regression),
+                ("added", Verdict::New),
+                ("gone", Verdict::Missing),
+            ]
+        );
+        assert_eq!(comparisons[1].p95, Some(50.0));
+        assert_eq!(comparisons[3].throughput, Some(-50.0));
+    }
+
+    #[test]
+    fn tolerance() {
+        let baseline = run(vec![tx("slower", 100, 200, 10.0)]);
+        let current = run(vec![tx("slower", 100, 300, 10.0)]);
+        assert_eq!(
+            verdicts(&current.compare(&baseline, 60.0)),
+            [("slower", Verdict::Unchanged)]
+        );
+    }
+}
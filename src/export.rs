@@ -0,0 +1,381 @@
+//! Exporting the metrics of the transactions of a run, for dashboards and other tools.
+
+use crate::{cli::MetricsExport, stats::TransactionStats};
+use anyhow::Context;
+use std::{fmt::Write, path::Path};
+
+/// Labels identifying the run, added to every exported transaction.
+#[derive(Clone, PartialEq, Eq, Debug, Default, serde::Serialize)]
+pub(crate) struct Labels {
+    /// The name of the scenario file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+    /// The identifier of the dataset loaded into trustify
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+}
+
+impl Labels {
+    /// Labels using the name of the scenario file, without its directory
+    pub fn new(scenario_file: Option<&str>, dataset: Option<String>) -> Self {
+        Self {
+            scenario: scenario_file.map(|file| {
+                Path::new(file)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| file.to_string())
+            }),
+            dataset,
+        }
+    }
+
+    /// The label names and values which are set
+    fn pairs(&self) -> Vec<(&'static str, &str)> {
+        [("scenario", &self.scenario), ("dataset", &self.dataset)]
+            .into_iter()
+            .filter_map(|(name, value)| value.as_deref().map(|value| (name, value)))
+            .collect()
+    }
+}
+
+/// The metrics of a transaction, latencies in milliseconds.
+#[derive(Clone, PartialEq, Debug, serde::Serialize)]
+pub(crate) struct Metrics {
+    pub name: String,
+    pub count: usize,
+    pub failed: usize,
+    pub min: usize,
+    pub mean: f64,
+    pub p50: usize,
+    pub p90: usize,
+    pub p95: usize,
+    pub p99: usize,
+    pub max: usize,
+    /// Runs per second
+    pub throughput: f64,
+}
+
+impl Metrics {
+    fn new(duration: usize, stats: &TransactionStats) -> Self {
+        Self {
+            name: stats.name.clone(),
+            count: stats.count,
+            failed: stats.failed,
+            min: stats.percentile(0.0),
+            mean: stats.mean(),
+            p50: stats.percentile(0.5),
+            p90: stats.percentile(0.9),
+            p95: stats.percentile(0.95),
+            p99: stats.percentile(0.99),
+            max: stats.percentile(1.0),
+            throughput: match duration {
+                0 => 0.0,
+                duration => stats.count as f64 / duration as f64,
+            },
+        }
+    }
+}
+
+/// The metrics of all transactions of a run.
+#[derive(Clone, PartialEq, Debug, serde::Serialize)]
+pub(crate) struct Export {
+    #[serde(flatten)]
+    pub labels: Labels,
+    /// The duration of the run, in seconds
+    pub duration: usize,
+    pub transactions: Vec<Metrics>,
+}
+
+/// The columns of the CSV export
+const CSV_HEADER: &str =
+    "scenario,dataset,transaction,count,failed,min,mean,p50,p90,p95,p99,max,throughput";
+
+/// An OpenMetrics metric family, with a single sample per transaction
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    value: fn(&Metrics) -> f64,
+}
+
+const FAMILIES: [Family; 5] = [
+    Family {
+        name: "loadtest_transaction_runs",
+        kind: "counter",
+        help: "The number of runs of a transaction",
+        value: |m| m.count as f64,
+    },
+    Family {
+        name: "loadtest_transaction_failures",
+        kind: "counter",
+        help: "The number of failed runs of a transaction",
+        value: |m| m.failed as f64,
+    },
+    Family {
+        name: "loadtest_transaction_duration_min_seconds",
+        kind: "gauge",
+        help: "The shortest run-time of a transaction",
+        value: |m| seconds(m.min),
+    },
+    Family {
+        name: "loadtest_transaction_duration_max_seconds",
+        kind: "gauge",
+        help: "The longest run-time of a transaction",
+        value: |m| seconds(m.max),
+    },
+    Family {
+        name: "loadtest_transaction_throughput",
+        kind: "gauge",
+        help: "The runs of a transaction per second",
+        value: |m| m.throughput,
+    },
+];
+
+/// A latency of a transaction, in milliseconds
+type Latency = fn(&Metrics) -> usize;
+
+/// The quantiles of the OpenMetrics latency summary
+const QUANTILES: [(&str, Latency); 4] = [
+    ("0.5", |m| m.p50),
+    ("0.9", |m| m.p90),
+    ("0.95", |m| m.p95),
+    ("0.99", |m| m.p99),
+];
+
+impl Export {
+    pub fn new(labels: Labels, duration: usize, stats: &[TransactionStats]) -> Self {
+        Self {
+            labels,
+            duration,
+            transactions: stats
+                .iter()
+                .map(|stats| Metrics::new(duration, stats))
+                .collect(),
+        }
+    }
+
+    /// Write the export to the files of the requested formats
+    pub fn store(&self, files: &MetricsExport) -> anyhow::Result<()> {
+        if let Some(file) = &files.export_json {
+            write(file, &self.json()?)?;
+        }
+        if let Some(file) = &files.export_csv {
+            write(file, &self.csv())?;
+        }
+        if let Some(file) = &files.export_openmetrics {
+            write(file, &self.openmetrics())?;
+        }
+        Ok(())
+    }
+
+    pub fn json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One row per transaction, latencies in milliseconds
+    pub fn csv(&self) -> String {
+        let mut result = format!("{CSV_HEADER}\n");
+        let scenario = csv_field(self.labels.scenario.as_deref().unwrap_or_default());
+        let dataset = csv_field(self.labels.dataset.as_deref().unwrap_or_default());
+
+        for m in &self.transactions {
+            let _ = writeln!(
+                result,
+                "{scenario},{dataset},{},{},{},{},{:.3},{},{},{},{},{},{:.3}",
+                csv_field(&m.name),
+                m.count,
+                m.failed,
+                m.min,
+                m.mean,
+                m.p50,
+                m.p90,
+                m.p95,
+                m.p99,
+                m.max,
+                m.throughput,
+            );
+        }
+
+        result
+    }
+
+    /// The OpenMetrics text format, latencies in seconds
+    pub fn openmetrics(&self) -> String {
+        let mut result = String::new();
+
+        for Family {
+            name,
+            kind,
+            help,
+            value,
+        } in FAMILIES
+        {
+            let _ = writeln!(result, "# TYPE {name} {kind}\n# HELP {name} {help}");
+            // the samples of counters carry a suffix
+            let sample = match kind {
+                "counter" => format!("{name}_total"),
+                _ => name.to_string(),
+            };
+            for m in &self.transactions {
+                let _ = writeln!(result, "{sample}{} {}", self.label_set(m, None), value(m));
+            }
+        }
+
+        let name = "loadtest_transaction_duration_seconds";
+        let _ = writeln!(
+            result,
+            "# TYPE {name} summary\n# HELP {name} The run-time of a transaction"
+        );
+        for m in &self.transactions {
+            for (quantile, value) in QUANTILES {
+                let _ = writeln!(
+                    result,
+                    "{name}{} {}",
+                    self.label_set(m, Some(quantile)),
+                    seconds(value(m))
+                );
+            }
+            let labels = self.label_set(m, None);
+            let _ = writeln!(
+                result,
+                "{name}_sum{labels} {}",
+                m.mean * m.count as f64 / 1000.0
+            );
+            let _ = writeln!(result, "{name}_count{labels} {}", m.count);
+        }
+
+        result.push_str("# EOF\n");
+        result
+    }
+
+    /// The OpenMetrics labels of a transaction, with an optional quantile
+    fn label_set(&self, metrics: &Metrics, quantile: Option<&str>) -> String {
+        let labels: Vec<_> = [("transaction", metrics.name.as_str())]
+            .into_iter()
+            .chain(self.labels.pairs())
+            .chain(quantile.map(|quantile| ("quantile", quantile)))
+            .map(|(name, value)| format!("{name}=\"{}\"", label_value(value)))
+            .collect();
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+/// Convert milliseconds to seconds
+fn seconds(millis: usize) -> f64 {
+    millis as f64 / 1000.0
+}
+
+/// Quote a CSV field, if required
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape an OpenMetrics label value
+pub(crate) fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write(file: &str, content: &str) -> anyhow::Result<()> {
+    std::fs::write(file, content).with_context(|| format!("writing metrics export: {file}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn export() -> Export {
+        let stats = TransactionStats {
+            name: "/api/v3/analysis/component?descendants=3&relationships=contains,dependency"
+                .into(),
+            times: [(10, 8), (100, 1), (1500, 1)].into_iter().collect(),
+            total_time: 1680,
+            count: 10,
+            failed: 1,
+        };
+        Export::new(
+            Labels::new(
+                Some("scenarios/full-20260412.json5"),
+                Some("20251104T064548Z".into()),
+            ),
+            5,
+            &[stats],
+        )
+    }
+
+    #[test]
+    fn metrics() {
+        let export = export();
+        assert_eq!(
+            export.labels.scenario.as_deref(),
+            Some("full-20260412.json5")
+        );
+        assert_eq!(
+            export.transactions[0],
+            Metrics {
+                name: export.transactions[0].name.clone(),
+                count: 10,
+                failed: 1,
+                min: 10,
+                mean: 168.0,
+                p50: 10,
+                p90: 100,
+                p95: 1500,
+                p99: 1500,
+                max: 1500,
+                throughput: 2.0,
+            }
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&export().json().expect("must serialize")).expect("must parse");
+        assert_eq!(json["dataset"], "20251104T064548Z");
+        assert_eq!(json["transactions"][0]["p95"], 1500);
+
+        let json = Export::new(Labels::default(), 5, &[])
+            .json()
+            .expect("must serialize");
+        assert!(!json.contains("dataset"));
+    }
+
+    #[test]
+    fn csv() {
+        let csv = export().csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "full-20260412.json5,20251104T064548Z,\"/api/v3/analysis/component?descendants=3&relationships=contains,dependency\",10,1,10,168.000,10,100,1500,1500,1500,2.000"
+        );
+    }
+
+    #[test]
+    fn openmetrics() {
+        let text = export().openmetrics();
+        let labels = r#"transaction="/api/v3/analysis/component?descendants=3&relationships=contains,dependency",scenario="full-20260412.json5",dataset="20251104T064548Z""#;
+
+        assert!(text.contains(&format!("loadtest_transaction_runs_total{{{labels}}} 10\n")));
+        assert!(text.contains(&format!(
+            "loadtest_transaction_duration_seconds{{{labels},quantile=\"0.95\"}} 1.5\n"
+        )));
+        assert!(text.contains(&format!(
+            "loadtest_transaction_duration_seconds_sum{{{labels}}} 1.68\n"
+        )));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+        assert_eq!(label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,389 @@
+//! Synthetic CSAF and OSV advisories.
+
+use super::sbom::SbomConfig;
+use crate::scenario::purl::CanonicalPurl;
+use chrono::{SecondsFormat, Utc};
+use rand::prelude::*;
+use serde_json::{Value, json};
+use std::fmt::{self, Display, Formatter};
+
+/// The format of generated advisories
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AdvisoryFormat {
+    /// CSAF 2.0
+    #[default]
+    Csaf,
+    /// OSV
+    Osv,
+}
+
+impl Display for AdvisoryFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Csaf => "csaf",
+            Self::Osv => "osv",
+        })
+    }
+}
+
+/// The shape of generated advisories.
+#[derive(Clone, PartialEq, Eq, Debug, clap::Args, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct AdvisoryConfig {
+    /// The format of the advisories
+    #[arg(long, value_enum, default_value_t)]
+    pub format: AdvisoryFormat,
+
+    /// The number of vulnerabilities of each advisory, aliases of the advisory for OSV
+    #[arg(
+        long,
+        default_value_t = 3,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub vulnerabilities: usize,
+
+    /// The number of products of each advisory
+    #[arg(long, default_value_t = 2)]
+    pub products: usize,
+
+    /// The number of affected package ranges of each product
+    #[arg(long, default_value_t = 10)]
+    pub affected: usize,
+}
+
+impl Default for AdvisoryConfig {
+    fn default() -> Self {
+        Self {
+            format: AdvisoryFormat::default(),
+            vulnerabilities: 3,
+            products: 2,
+            affected: 10,
+        }
+    }
+}
+
+/// Generates advisories affecting known packages.
+pub(crate) struct AdvisoryGenerator {
+    config: AdvisoryConfig,
+    purls: Vec<CanonicalPurl>,
+}
+
+/// An affected range of a package: all versions up to (and including) the version of a known purl.
+struct Affected {
+    /// The purl, without a version
+    purl: CanonicalPurl,
+    /// The last affected version, all versions are affected if missing
+    version: Option<String>,
+}
+
+impl From<&CanonicalPurl> for Affected {
+    fn from(purl: &CanonicalPurl) -> Self {
+        let mut purl = purl.clone();
+        let version = purl.version.take();
+        Self { purl, version }
+    }
+}
+
+impl AdvisoryGenerator {
+    /// Create a new generator, with the affected ranges matching the provided purls.
+    ///
+    /// Invalid purls are skipped. If no purl is left, the packages of generated SBOMs are used.
+    pub fn new(config: AdvisoryConfig, purls: &[String]) -> Self {
+        let mut purls: Vec<CanonicalPurl> = purls
+            .iter()
+            .filter_map(|purl| {
+                purl.parse()
+                    .inspect_err(|err| log::warn!("Skipping invalid purl '{purl}': {err}"))
+                    .ok()
+            })
+            .collect();
+
+        if purls.is_empty() {
+            purls = SbomConfig::default().purls();
+        }
+
+        Self { config, purls }
+    }
+
+    pub fn config(&self) -> &AdvisoryConfig {
+        &self.config
+    }
+
+    /// Generate a new advisory, with a unique ID.
+    pub fn generate(&self, rng: &mut impl Rng) -> Value {
+        let id = format!("LOADTEST-{}", uuid::Uuid::new_v4());
+
+        // the same CVE may show up in several advisories, like it does in real data
+        let mut cves: Vec<_> = (0..self.config.vulnerabilities)
+            .map(|_| format!("CVE-2099-{}", rng.random_range(10000..100000)))
+            .collect();
+        cves.sort_unstable();
+        cves.dedup();
+
+        let products: Vec<Vec<Affected>> = (0..self.config.products)
+            .map(|_| {
+                self.purls
+                    .sample(rng, self.config.affected)
+                    .map(Affected::from)
+                    .collect()
+            })
+            .collect();
+
+        match self.config.format {
+            AdvisoryFormat::Csaf => csaf(&id, &cves, &products),
+            AdvisoryFormat::Osv => osv(&id, &cves, &products),
+        }
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn csaf(id: &str, cves: &[String], products: &[Vec<Affected>]) -> Value {
+    let product_id = |product: usize, package: usize| format!("CSAFPID-{product}-{package}");
+
+    let branches: Vec<_> = products
+        .iter()
+        .enumerate()
+        .map(|(product, affected)| {
+            let ranges: Vec<_> = affected
+                .iter()
+                .enumerate()
+                .map(|(package, affected)| {
+                    let range = match &affected.version {
+                        Some(version) => format!("vers:{}/<={version}", affected.purl.ty),
+                        None => format!("vers:{}/*", affected.purl.ty),
+                    };
+                    json!({
+                        "category": "product_version_range",
+                        "name": range,
+                        "product": {
+                            "product_id": product_id(product, package),
+                            "name": format!("{} {range}", affected.purl.name),
+                            "product_identification_helper": {
+                                "purl": affected.purl.to_string(),
+                            },
+                        },
+                    })
+                })
+                .collect();
+
+            json!({
+                "category": "product_name",
+                "name": format!("loadtest-product-{product}"),
+                "branches": ranges,
+            })
+        })
+        .collect();
+
+    let affected: Vec<_> = products
+        .iter()
+        .enumerate()
+        .flat_map(|(product, affected)| {
+            (0..affected.len()).map(move |package| product_id(product, package))
+        })
+        .collect();
+
+    let now = now();
+
+    json!({
+        "document": {
+            "category": "csaf_security_advisory",
+            "csaf_version": "2.0",
+            "title": format!("Load test advisory {id}"),
+            "publisher": {
+                "category": "vendor",
+                "name": "Trustify Loadtest",
+                "namespace": "https://trustify.io/loadtest",
+            },
+            "tracking": {
+                "id": id,
+                "status": "final",
+                "version": "1",
+                "initial_release_date": now,
+                "current_release_date": now,
+                "revision_history": [{ "date": now, "number": "1", "summary": "Initial version" }],
+            },
+        },
+        "product_tree": {
+            "branches": [{
+                "category": "vendor",
+                "name": "Trustify Loadtest",
+                "branches": branches,
+            }],
+        },
+        "vulnerabilities": cves.iter().map(|cve| json!({
+            "cve": cve,
+            "title": format!("Load test vulnerability {cve}"),
+            "notes": [{ "category": "description", "text": "A vulnerability generated by the load test" }],
+            "product_status": { "known_affected": affected },
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// The OSV ecosystem of a purl type
+fn ecosystem(ty: &str) -> &str {
+    match ty {
+        "cargo" => "crates.io",
+        "golang" => "Go",
+        "maven" => "Maven",
+        "pypi" => "PyPI",
+        "rpm" => "Red Hat",
+        ty => ty,
+    }
+}
+
+fn osv(id: &str, cves: &[String], products: &[Vec<Affected>]) -> Value {
+    let affected: Vec<_> = products
+        .iter()
+        .flatten()
+        .map(|affected| {
+            let name = match (affected.purl.ty.as_str(), &affected.purl.namespace) {
+                ("maven", Some(namespace)) => format!("{namespace}:{}", affected.purl.name),
+                (_, Some(namespace)) => format!("{namespace}/{}", affected.purl.name),
+                (_, None) => affected.purl.name.clone(),
+            };
+            let mut events = vec![json!({ "introduced": "0" })];
+            if let Some(version) = &affected.version {
+                events.push(json!({ "last_affected": version }));
+            }
+
+            json!({
+                "package": {
+                    "ecosystem": ecosystem(&affected.purl.ty),
+                    "name": name,
+                    "purl": affected.purl.to_string(),
+                },
+                "ranges": [{ "type": "ECOSYSTEM", "events": events }],
+            })
+        })
+        .collect();
+
+    let now = now();
+
+    json!({
+        "schema_version": "1.6.0",
+        "id": id,
+        "modified": now,
+        "published": now,
+        "aliases": cves,
+        "summary": format!("Load test advisory {id}"),
+        "affected": affected,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    fn generator(config: AdvisoryConfig) -> AdvisoryGenerator {
+        AdvisoryGenerator::new(
+            config,
+            &[
+                "pkg:rpm/redhat/openssl@3.0.7?arch=x86_64".into(),
+                "pkg:maven/org.apache/commons-text@1.9".into(),
+                "not a purl".into(),
+            ],
+        )
+    }
+
+    #[test]
+    fn known_purls() {
+        assert_eq!(generator(AdvisoryConfig::default()).purls.len(), 2);
+    }
+
+    #[test]
+    fn fallback() {
+        let generator = AdvisoryGenerator::new(AdvisoryConfig::default(), &[]);
+        assert_eq!(generator.purls, SbomConfig::default().purls());
+    }
+
+    #[test]
+    fn csaf() {
+        let document = generator(AdvisoryConfig {
+            vulnerabilities: 2,
+            products: 3,
+            affected: 1,
+            ..Default::default()
+        })
+        .generate(&mut rng());
+
+        assert_eq!(document["document"]["csaf_version"], "2.0");
+        let products = &document["product_tree"]["branches"][0]["branches"];
+        assert_eq!(products.as_array().map(Vec::len), Some(3));
+        assert!(
+            products[0]["branches"][0]["name"]
+                .as_str()
+                .is_some_and(|name| name.starts_with("vers:"))
+        );
+        assert_eq!(
+            document["vulnerabilities"].as_array().map(Vec::len),
+            Some(2)
+        );
+        assert_eq!(
+            document["vulnerabilities"][0]["product_status"]["known_affected"],
+            json!(["CSAFPID-0-0", "CSAFPID-1-0", "CSAFPID-2-0"])
+        );
+    }
+
+    #[test]
+    fn osv() {
+        let document = generator(AdvisoryConfig {
+            format: AdvisoryFormat::Osv,
+            vulnerabilities: 2,
+            products: 1,
+            // more than known, limited to the known purls
+            affected: 5,
+        })
+        .generate(&mut rng());
+
+        assert_eq!(document["aliases"].as_array().map(Vec::len), Some(2));
+        let affected = document["affected"].as_array().cloned().unwrap_or_default();
+        assert_eq!(affected.len(), 2);
+
+        let openssl = affected
+            .iter()
+            .find(|affected| affected["package"]["ecosystem"] == "Red Hat")
+            .expect("must have the rpm");
+        assert_eq!(
+            openssl["package"]["purl"],
+            "pkg:rpm/redhat/openssl?arch=x86_64"
+        );
+        assert_eq!(
+            openssl["ranges"][0]["events"],
+            json!([{ "introduced": "0" }, { "last_affected": "3.0.7" }])
+        );
+
+        let maven = affected
+            .iter()
+            .find(|affected| affected["package"]["ecosystem"] == "Maven")
+            .expect("must have the maven package");
+        assert_eq!(maven["package"]["name"], "org.apache:commons-text");
+    }
+
+    #[test]
+    fn unique() {
+        let generator = generator(AdvisoryConfig::default());
+        assert_ne!(
+            generator.generate(&mut rng())["document"]["tracking"]["id"],
+            generator.generate(&mut rng())["document"]["tracking"]["id"]
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Synthetic documents, for load tests ingesting data instead of reading it.
+
+pub mod advisory;
+pub mod sbom;
+
+use crate::scenario::purl::CanonicalPurl;
+use std::collections::BTreeMap;
+
+/// The package URL types used when none are configured
+pub(crate) const DEFAULT_PURL_TYPES: [&str; 3] = ["rpm", "maven", "npm"];
+
+/// Create the purl of a generated package.
+///
+/// Packages with the same type and index share the same purl across all generated documents, like a common
+/// dependency would in real SBOMs.
+pub(crate) fn package_purl(ty: &str, index: usize) -> CanonicalPurl {
+    let (namespace, qualifiers): (_, &[(&str, &str)]) = match ty {
+        "rpm" => (Some("loadtest"), &[("arch", "x86_64")]),
+        "maven" => (Some("io.trustify.loadtest"), &[("type", "jar")]),
+        "golang" => (Some("github.com/trustify/loadtest"), &[]),
+        _ => (None, &[]),
+    };
+
+    CanonicalPurl {
+        ty: ty.to_string(),
+        namespace: namespace.map(ToString::to_string),
+        name: format!("loadtest-component-{index}"),
+        version: Some(format!("1.{}.0", index % 10)),
+        qualifiers: qualifiers
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn purls() {
+        assert_eq!(
+            package_purl("rpm", 12).to_string(),
+            "pkg:rpm/loadtest/loadtest-component-12@1.2.0?arch=x86_64"
+        );
+        assert_eq!(
+            package_purl("npm", 3).to_string(),
+            "pkg:npm/loadtest-component-3@1.3.0"
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Synthetic SPDX and CycloneDX SBOMs.
+
+use super::{DEFAULT_PURL_TYPES, package_purl};
+use crate::scenario::purl::CanonicalPurl;
+use chrono::{SecondsFormat, Utc};
+use rand::prelude::*;
+use serde_json::{Value, json};
+use std::fmt::{self, Display, Formatter};
+
+/// The format of generated SBOMs
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SbomFormat {
+    /// SPDX 2.3
+    #[default]
+    Spdx,
+    /// CycloneDX 1.5
+    #[value(name = "cyclonedx")]
+    CycloneDx,
+}
+
+impl Display for SbomFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Spdx => "spdx",
+            Self::CycloneDx => "cyclonedx",
+        })
+    }
+}
+
+/// The shape of generated SBOMs.
+#[derive(Clone, PartialEq, Eq, Debug, clap::Args, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SbomConfig {
+    /// The format of the SBOMs
+    #[arg(long, value_enum, default_value_t)]
+    pub format: SbomFormat,
+
+    /// The number of packages of each SBOM, in addition to the described product
+    #[arg(long, default_value_t = 100)]
+    pub packages: usize,
+
+    /// The depth of the dependency tree, `1` for direct dependencies of the product only
+    #[arg(
+        long,
+        default_value_t = 3,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub depth: usize,
+
+    /// The package URL types of the packages, used one after the other
+    #[arg(long = "purl-type", value_delimiter = ',', default_values = DEFAULT_PURL_TYPES)]
+    pub purl_types: Vec<String>,
+}
+
+impl Default for SbomConfig {
+    fn default() -> Self {
+        Self {
+            format: SbomFormat::default(),
+            packages: 100,
+            depth: 3,
+            purl_types: DEFAULT_PURL_TYPES.map(ToString::to_string).to_vec(),
+        }
+    }
+}
+
+/// A package of a generated SBOM
+struct Package {
+    /// Unique within the document, usable as SPDX ID or CycloneDX `bom-ref`
+    id: String,
+    name: String,
+    version: String,
+    purl: CanonicalPurl,
+}
+
+impl SbomConfig {
+    /// The purls of the packages, which are the same for all generated SBOMs.
+    pub fn purls(&self) -> Vec<CanonicalPurl> {
+        (0..self.packages)
+            .map(|index| match self.purl_types.is_empty() {
+                true => package_purl("generic", index),
+                false => package_purl(&self.purl_types[index % self.purl_types.len()], index),
+            })
+            .collect()
+    }
+
+    /// Generate a new SBOM. Every document has a unique name and namespace (or serial number), so that it gets
+    /// ingested as a new document instead of being deduplicated.
+    pub fn generate(&self, rng: &mut impl Rng) -> Value {
+        let id = uuid::Uuid::new_v4();
+        let name = format!("loadtest-product-{id}");
+
+        let product = Package {
+            id: "product".into(),
+            name: name.clone(),
+            version: "1.0.0".into(),
+            purl: CanonicalPurl {
+                ty: "generic".into(),
+                namespace: Some("loadtest".into()),
+                name: name.clone(),
+                version: Some("1.0.0".into()),
+                qualifiers: Default::default(),
+            },
+        };
+
+        let packages: Vec<_> = self
+            .purls()
+            .into_iter()
+            .enumerate()
+            .map(|(index, purl)| Package {
+                id: format!("package-{index}"),
+                name: purl.name.clone(),
+                version: purl.version.clone().unwrap_or_default(),
+                purl,
+            })
+            .collect();
+
+        let dependencies = tree(self.packages, self.depth, rng);
+
+        match self.format {
+            SbomFormat::Spdx => spdx(id, &product, &packages, &dependencies),
+            SbomFormat::CycloneDx => cyclonedx(id, &product, &packages, &dependencies),
+        }
+    }
+}
+
+/// Arrange packages in a dependency tree of the requested depth.
+///
+/// Returns an edge `(parent, child)` for every package, with `None` being the described product. Each level of the
+/// tree has roughly the same number of packages, and each package depends on a random package of the level above.
+fn tree(packages: usize, depth: usize, rng: &mut impl Rng) -> Vec<(Option<usize>, usize)> {
+    let depth = depth.clamp(1, packages.max(1));
+    let level = |index: usize| index * depth / packages;
+
+    // the first package of the previous and of the current level
+    let mut previous = 0;
+    let mut current = 0;
+
+    (0..packages)
+        .map(|index| {
+            if level(index) != level(current) {
+                previous = current;
+                current = index;
+            }
+            let parent = (level(index) > 0).then(|| rng.random_range(previous..current));
+            (parent, index)
+        })
+        .collect()
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn spdx(
+    id: uuid::Uuid,
+    product: &Package,
+    packages: &[Package],
+    dependencies: &[(Option<usize>, usize)],
+) -> Value {
+    let spdx_id = |package: &Package| format!("SPDXRef-{}", package.id);
+    let package = |package: &Package| {
+        json!({
+            "SPDXID": spdx_id(package),
+            "name": package.name,
+            "versionInfo": package.version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": package.purl.to_string(),
+            }],
+        })
+    };
+
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": spdx_id(product),
+    })];
+    relationships.extend(dependencies.iter().map(|(parent, child)| {
+        json!({
+            "spdxElementId": spdx_id(parent.map(|parent| &packages[parent]).unwrap_or(product)),
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": spdx_id(&packages[*child]),
+        })
+    }));
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": product.name,
+        "documentNamespace": format!("https://trustify.io/loadtest/{id}"),
+        "creationInfo": {
+            "created": now(),
+            "creators": ["Tool: trustify-loadtest"],
+        },
+        "documentDescribes": [spdx_id(product)],
+        "packages": std::iter::once(product).chain(packages).map(package).collect::<Vec<_>>(),
+        "relationships": relationships,
+    })
+}
+
+fn cyclonedx(
+    id: uuid::Uuid,
+    product: &Package,
+    packages: &[Package],
+    dependencies: &[(Option<usize>, usize)],
+) -> Value {
+    let component = |package: &Package, ty: &str| {
+        json!({
+            "type": ty,
+            "bom-ref": package.id,
+            "name": package.name,
+            "version": package.version,
+            "purl": package.purl.to_string(),
+        })
+    };
+
+    // every component gets an entry, including the ones without dependencies
+    let mut depends_on = vec![Vec::new(); packages.len() + 1];
+    for (parent, child) in dependencies {
+        depends_on[parent.map(|parent| parent + 1).unwrap_or_default()].push(&packages[*child].id);
+    }
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{id}"),
+        "version": 1,
+        "metadata": {
+            "timestamp": now(),
+            "tools": {
+                "components": [{ "type": "application", "name": "trustify-loadtest" }],
+            },
+            "component": component(product, "application"),
+        },
+        "components": packages.iter().map(|package| component(package, "library")).collect::<Vec<_>>(),
+        "dependencies": std::iter::once(product)
+            .chain(packages)
+            .zip(depends_on)
+            .map(|(package, depends_on)| json!({ "ref": package.id, "dependsOn": depends_on }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    #[test]
+    fn tree_depth() {
+        let edges = tree(10, 3, &mut rng());
+        let mut levels = vec![0; 10];
+        for (parent, child) in &edges {
+            levels[*child] = parent.map(|parent| levels[parent] + 1).unwrap_or_default();
+        }
+        assert_eq!(levels, [0, 0, 0, 0, 1, 1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn tree_deeper_than_packages() {
+        let edges = tree(2, 5, &mut rng());
+        assert_eq!(edges, [(None, 0), (Some(0), 1)]);
+        assert!(tree(0, 5, &mut rng()).is_empty());
+    }
+
+    #[test]
+    fn spdx() {
+        let config = SbomConfig {
+            packages: 4,
+            depth: 2,
+            purl_types: vec!["rpm".into(), "npm".into()],
+            ..Default::default()
+        };
+        let document = config.generate(&mut rng());
+
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(document["documentDescribes"], json!(["SPDXRef-product"]));
+        assert_eq!(document["packages"].as_array().map(Vec::len), Some(5));
+        // one DESCRIBES, one DEPENDS_ON for every package
+        assert_eq!(document["relationships"].as_array().map(Vec::len), Some(5));
+        assert_eq!(
+            document["packages"][2]["externalRefs"][0]["referenceLocator"],
+            "pkg:npm/loadtest-component-1@1.1.0"
+        );
+    }
+
+    #[test]
+    fn cyclonedx() {
+        let config = SbomConfig {
+            format: SbomFormat::CycloneDx,
+            packages: 3,
+            depth: 1,
+            ..Default::default()
+        };
+        let document = config.generate(&mut rng());
+
+        assert_eq!(document["specVersion"], "1.5");
+        assert_eq!(document["components"].as_array().map(Vec::len), Some(3));
+        assert_eq!(document["dependencies"][0]["ref"], "product");
+        assert_eq!(
+            document["dependencies"][0]["dependsOn"],
+            json!(["package-0", "package-1", "package-2"])
+        );
+        assert_eq!(document["dependencies"][1]["dependsOn"], json!([]));
+    }
+
+    #[test]
+    fn unique() {
+        let config = SbomConfig::default();
+        let first = config.generate(&mut rng());
+        let second = config.generate(&mut rng());
+        assert_ne!(first["name"], second["name"]);
+        assert_ne!(first["documentNamespace"], second["documentNamespace"]);
+    }
+
+    #[test]
+    fn workload_config() {
+        let config: SbomConfig =
+            serde_json5::from_str(r#"{ format: "cyclonedx", packages: 10 }"#).expect("must parse");
+        assert_eq!(config.format, SbomFormat::CycloneDx);
+        assert_eq!(config.packages, 10);
+        assert_eq!(config.depth, 3);
+    }
+}
@@ -0,0 +1,314 @@
+//! Snapshots of the labels modified by the label users, restoring them after a run.
+
+use crate::oidc::OpenIdTokenProvider;
+use anyhow::{Context, bail};
+use reqwest_12::{Client, RequestBuilder};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+use tokio::sync::Mutex;
+use urlencoding::encode;
+
+/// The kind of an entity carrying labels
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Kind {
+    Sbom,
+    Advisory,
+}
+
+impl Kind {
+    fn path(&self, id: &str) -> String {
+        let kind = match self {
+            Self::Sbom => "sbom",
+            Self::Advisory => "advisory",
+        };
+        format!("/api/v3/{kind}/{}", encode(id))
+    }
+}
+
+/// The original labels of an entity
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Entry {
+    pub kind: Kind,
+    pub id: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Reads and writes labels using the REST API, outside of goose.
+#[derive(Clone)]
+pub(crate) struct LabelClient {
+    client: Client,
+    host: String,
+    provider: Option<OpenIdTokenProvider>,
+}
+
+impl LabelClient {
+    pub fn new(host: String, provider: Option<OpenIdTokenProvider>) -> Self {
+        Self {
+            client: Client::new(),
+            host,
+            provider,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.host.trim_end_matches('/'))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<reqwest_12::Response> {
+        let request = match &self.provider {
+            Some(provider) => provider.authorize(request).await?,
+            None => request,
+        };
+
+        Ok(request.send().await?.error_for_status()?)
+    }
+
+    /// Get the current labels of an entity
+    pub async fn get(&self, kind: Kind, id: &str) -> anyhow::Result<BTreeMap<String, String>> {
+        let path = kind.path(id);
+        let body: Value = self
+            .send(self.client.get(self.url(&path)))
+            .await
+            .with_context(|| format!("failed to get {path}"))?
+            .json()
+            .await
+            .with_context(|| format!("failed to read {path}"))?;
+
+        Ok(labels(&body))
+    }
+
+    /// Replace the labels of an entity with the ones of the entry
+    pub async fn set(&self, entry: &Entry) -> anyhow::Result<()> {
+        let path = format!("{}/label", entry.kind.path(&entry.id));
+        self.send(self.client.put(self.url(&path)).json(&entry.labels))
+            .await
+            .with_context(|| format!("failed to put {path}"))?;
+        Ok(())
+    }
+}
+
+/// The labels of an entity response, ignoring non-string values.
+fn labels(body: &Value) -> BTreeMap<String, String> {
+    body["labels"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+        .collect()
+}
+
+/// The original labels of all entities modified during a run.
+///
+/// Labels are recorded before an entity gets modified for the first time, and appended to a file as JSON lines. So
+/// they can still be restored using the `cleanup` command if a run didn't finish.
+pub(crate) struct LabelSnapshot {
+    client: LabelClient,
+    file: PathBuf,
+    recorded: Mutex<BTreeSet<(Kind, String)>>,
+}
+
+impl LabelSnapshot {
+    /// Create a new snapshot, continuing the one left behind by a previous run.
+    pub fn new(client: LabelClient, file: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let file = file.into();
+        let recorded = load(&file)?
+            .into_iter()
+            .map(|entry| (entry.kind, entry.id))
+            .collect();
+
+        Ok(Self {
+            client,
+            file,
+            recorded: Mutex::new(recorded),
+        })
+    }
+
+    /// Record the labels of an entity, unless they already are.
+    pub async fn record(&self, kind: Kind, id: &str) -> anyhow::Result<()> {
+        let key = (kind, id.to_string());
+        if self.recorded.lock().await.contains(&key) {
+            return Ok(());
+        }
+
+        // Not locked while fetching. Another user may record the same entity concurrently, but only the first one
+        // gets stored, before any of them modified it.
+        let labels = self.client.get(kind, id).await?;
+
+        let mut recorded = self.recorded.lock().await;
+        if recorded.insert(key) {
+            append(
+                &self.file,
+                &Entry {
+                    kind,
+                    id: id.to_string(),
+                    labels,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Restore all recorded labels, returning the number of restored entities.
+    pub async fn restore(&self) -> anyhow::Result<usize> {
+        let mut recorded = self.recorded.lock().await;
+        let restored = restore(&self.client, &self.file).await?;
+        recorded.clear();
+        Ok(restored)
+    }
+}
+
+/// Load the entries of a snapshot file, keeping the first entry of every entity.
+///
+/// A missing file is an empty snapshot.
+pub(crate) fn load(file: &Path) -> anyhow::Result<Vec<Entry>> {
+    match File::open(file) {
+        Ok(reader) => parse(BufReader::new(reader))
+            .with_context(|| format!("reading label snapshot: {}", file.display())),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err).with_context(|| format!("opening label snapshot: {}", file.display())),
+    }
+}
+
+fn parse(reader: impl BufRead) -> anyhow::Result<Vec<Entry>> {
+    let mut seen = BTreeSet::new();
+    let mut result = vec![];
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line)
+            .with_context(|| format!("invalid entry in line {}", index + 1))?;
+        if seen.insert((entry.kind, entry.id.clone())) {
+            result.push(entry);
+        }
+    }
+
+    Ok(result)
+}
+
+fn append(file: &Path, entry: &Entry) -> anyhow::Result<()> {
+    let mut writer = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .with_context(|| format!("opening label snapshot: {}", file.display()))?;
+    writeln!(writer, "{}", serde_json::to_string(entry)?)
+        .with_context(|| format!("writing label snapshot: {}", file.display()))
+}
+
+/// Restore the labels of a snapshot file, returning the number of restored entities.
+///
+/// The file is removed once all labels are restored, and kept otherwise to try again.
+pub(crate) async fn restore(client: &LabelClient, file: &Path) -> anyhow::Result<usize> {
+    let entries = load(file)?;
+
+    let mut failed = 0;
+    for entry in &entries {
+        if let Err(err) = client.set(entry).await {
+            log::warn!("Unable to restore labels: {err:#}");
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "failed to restore the labels of {failed} of {} entities, keeping {}",
+            entries.len(),
+            file.display()
+        );
+    }
+
+    match std::fs::remove_file(file) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("removing label snapshot: {}", file.display()))
+        }
+        _ => Ok(entries.len()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(kind: Kind, id: &str, labels: &[(&str, &str)]) -> Entry {
+        Entry {
+            kind,
+            id: id.into(),
+            labels: labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(
+            Kind::Sbom.path("urn:uuid:0195"),
+            "/api/v3/sbom/urn%3Auuid%3A0195"
+        );
+        assert_eq!(Kind::Advisory.path("0195"), "/api/v3/advisory/0195");
+    }
+
+    #[test]
+    fn response_labels() {
+        let body = json!({ "id": "a", "labels": { "type": "product", "count": 1 } });
+        assert_eq!(
+            labels(&body),
+            entry(Kind::Sbom, "a", &[("type", "product")]).labels
+        );
+        assert!(labels(&json!({ "id": "a" })).is_empty());
+    }
+
+    #[test]
+    fn first_entry_wins() {
+        let lines = [
+            json!({ "kind": "sbom", "id": "a", "labels": { "type": "product" } }),
+            json!({ "kind": "advisory", "id": "a", "labels": {} }),
+            json!({ "kind": "sbom", "id": "a", "labels": { "load-test": "true" } }),
+        ]
+        .map(|line| line.to_string())
+        .join("\n");
+
+        let entries = parse(format!("{lines}\n\n").as_bytes()).expect("must parse");
+        assert_eq!(
+            entries,
+            [
+                entry(Kind::Sbom, "a", &[("type", "product")]),
+                entry(Kind::Advisory, "a", &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_line() {
+        let err = parse(r#"{"kind": "sbom"}"#.as_bytes()).expect_err("must fail");
+        assert_eq!(err.to_string(), "invalid entry in line 1");
+    }
+
+    #[test]
+    fn append_and_load() {
+        let file = std::env::temp_dir().join(format!("labels-{}.jsonl", uuid::Uuid::new_v4()));
+        assert!(load(&file).expect("missing file is empty").is_empty());
+
+        let first = entry(Kind::Sbom, "a", &[("type", "product")]);
+        append(&file, &first).expect("must append");
+        append(&file, &entry(Kind::Sbom, "a", &[])).expect("must append");
+        let loaded = load(&file);
+        let _ = std::fs::remove_file(&file);
+
+        assert_eq!(loaded.expect("must load"), [first]);
+    }
+}
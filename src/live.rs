@@ -0,0 +1,383 @@
+//! A live metrics endpoint, exposing the progress of a run to Prometheus while it is running.
+
+use crate::{
+    export::label_value,
+    oidc::OpenIdTokenProvider,
+    utils::{GooseUserData, wrap_transactions},
+};
+use anyhow::Context;
+use goose::goose::{Scenario, Transaction};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+    task::JoinHandle,
+};
+
+/// The upper bounds of the latency histogram buckets, in seconds
+const BUCKETS: [f64; 13] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// The maximum size of a request head, larger requests are cut off
+const MAX_REQUEST: u64 = 8 * 1024;
+
+/// The latencies of a transaction.
+#[derive(Clone, PartialEq, Debug, Default)]
+struct Histogram {
+    /// The number of runs not exceeding the bound of each bucket
+    buckets: [u64; BUCKETS.len()],
+    /// The total run-time, in seconds
+    sum: f64,
+    count: u64,
+    failed: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, time: Duration, failed: bool) {
+        let seconds = time.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+        if failed {
+            self.failed += 1;
+        }
+    }
+}
+
+/// The metrics of a run, recorded while it is running.
+///
+/// Metrics accumulate over all stages of a load profile, except for the active users.
+pub(crate) struct LiveMetrics {
+    transactions: Mutex<BTreeMap<String, Histogram>>,
+    /// Failed requests by name and status code, `0` for requests without a response
+    errors: Mutex<BTreeMap<(String, u16), u64>>,
+    /// Users which ran a transaction in the current goose run
+    users: AtomicU64,
+    in_flight: AtomicU64,
+    provider: Option<OpenIdTokenProvider>,
+}
+
+impl LiveMetrics {
+    pub fn new(provider: Option<OpenIdTokenProvider>) -> Self {
+        Self {
+            transactions: Default::default(),
+            errors: Default::default(),
+            users: AtomicU64::new(0),
+            in_flight: AtomicU64::new(0),
+            provider,
+        }
+    }
+
+    /// Reset the active users, before starting a new goose run
+    pub fn reset_users(&self) {
+        self.users.store(0, Ordering::Relaxed);
+    }
+
+    /// Record the status of a request, counting it as an error if it failed
+    pub async fn record_status(&self, name: &str, status: u16) {
+        if status == 0 || status >= 400 {
+            *self
+                .errors
+                .lock()
+                .await
+                .entry((name.to_string(), status))
+                .or_default() += 1;
+        }
+    }
+
+    async fn observe(&self, name: &str, time: Duration, failed: bool) {
+        self.transactions
+            .lock()
+            .await
+            .entry(name.to_string())
+            .or_default()
+            .observe(time, failed);
+    }
+
+    /// Measure the transactions of a scenario, and record the status of their requests.
+    pub fn measure(self: &Arc<Self>, scenario: Scenario) -> Scenario {
+        wrap_transactions(scenario, |transaction| self.measured(transaction))
+    }
+
+    fn measured(self: &Arc<Self>, mut transaction: Transaction) -> Transaction {
+        let function = transaction.function.clone();
+        let name = transaction.name.clone();
+        let metrics = self.clone();
+
+        transaction.function = Arc::new(move |user| {
+            let function = function.clone();
+            let name = name.clone();
+            let metrics = metrics.clone();
+            Box::pin(async move {
+                // the requests of the user record their status using the session data
+                let data = GooseUserData::of(user);
+                if data.live_metrics.is_none() {
+                    data.live_metrics = Some(metrics.clone());
+                    metrics.users.fetch_add(1, Ordering::Relaxed);
+                }
+
+                metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+                let start = Instant::now();
+                let result = function(user).await;
+                metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+                metrics
+                    .observe(&name, start.elapsed(), result.is_err())
+                    .await;
+
+                result
+            })
+        });
+
+        transaction
+    }
+
+    /// The metrics in the OpenMetrics text format
+    pub async fn render(&self) -> String {
+        let mut result = String::new();
+
+        let transactions = self.transactions.lock().await;
+        let name = "loadtest_transaction_latency_seconds";
+        let _ = writeln!(
+            result,
+            "# TYPE {name} histogram\n# HELP {name} The run-time of a transaction"
+        );
+        for (transaction, histogram) in transactions.iter() {
+            let transaction = label_value(transaction);
+            for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    result,
+                    "{name}_bucket{{transaction=\"{transaction}\",le=\"{bound:?}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                result,
+                "{name}_bucket{{transaction=\"{transaction}\",le=\"+Inf\"}} {}\n\
+                 {name}_sum{{transaction=\"{transaction}\"}} {}\n\
+                 {name}_count{{transaction=\"{transaction}\"}} {}",
+                histogram.count, histogram.sum, histogram.count
+            );
+        }
+
+        let name = "loadtest_transaction_failures";
+        let _ = writeln!(
+            result,
+            "# TYPE {name} counter\n# HELP {name} The number of failed runs of a transaction"
+        );
+        for (transaction, histogram) in transactions.iter() {
+            let _ = writeln!(
+                result,
+                "{name}_total{{transaction=\"{}\"}} {}",
+                label_value(transaction),
+                histogram.failed
+            );
+        }
+        drop(transactions);
+
+        let name = "loadtest_request_errors";
+        let _ = writeln!(
+            result,
+            "# TYPE {name} counter\n# HELP {name} The number of failed requests by status code, 0 without a response"
+        );
+        for ((request, status), count) in self.errors.lock().await.iter() {
+            let _ = writeln!(
+                result,
+                "{name}_total{{request=\"{}\",status=\"{status}\"}} {count}",
+                label_value(request)
+            );
+        }
+
+        let gauges = [
+            (
+                "loadtest_users",
+                "The users which started running transactions",
+                &self.users,
+            ),
+            (
+                "loadtest_transactions_in_flight",
+                "The transactions currently running",
+                &self.in_flight,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            let _ = writeln!(
+                result,
+                "# TYPE {name} gauge\n# HELP {name} {help}\n{name} {}",
+                value.load(Ordering::Relaxed)
+            );
+        }
+
+        if let Some(provider) = &self.provider {
+            let tokens = provider.metrics();
+            let name = "loadtest_oidc_tokens";
+            let _ = writeln!(
+                result,
+                "# TYPE {name} counter\n# HELP {name} The number of OIDC token requests by outcome"
+            );
+            for (outcome, count) in [
+                ("issued", tokens.issued),
+                ("refreshed", tokens.refreshed),
+                ("failed", tokens.failed),
+            ] {
+                let _ = writeln!(result, "{name}_total{{outcome=\"{outcome}\"}} {count}");
+            }
+        }
+
+        result.push_str("# EOF\n");
+        result
+    }
+}
+
+/// Serve the metrics on `/metrics`, until the returned task gets aborted.
+pub(crate) async fn serve(
+    metrics: Arc<LiveMetrics>,
+    address: SocketAddr,
+) -> anyhow::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("binding the live metrics endpoint to {address}"))?;
+    log::info!(
+        "Serving live metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    log::warn!("Unable to accept a live metrics connection: {err}");
+                    continue;
+                }
+            };
+            let metrics = metrics.clone();
+            tokio::spawn(async move {
+                if let Err(err) = respond(stream, &metrics).await {
+                    log::debug!("Unable to serve live metrics: {err}");
+                }
+            });
+        }
+    }))
+}
+
+async fn respond(mut stream: TcpStream, metrics: &LiveMetrics) -> std::io::Result<()> {
+    let (read, mut write) = stream.split();
+    let mut reader = BufReader::new(read.take(MAX_REQUEST));
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    // skip the headers, up to the empty line
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let (status, content_type, body) = match request_target(&request_line) {
+        Some("/metrics") => ("200 OK", CONTENT_TYPE, metrics.render().await),
+        _ => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+    };
+
+    write
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await?;
+    write.shutdown().await
+}
+
+/// The path of a `GET` request, without its query
+fn request_target(request_line: &str) -> Option<&str> {
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => target.split('?').next(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn histogram() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(5), false);
+        histogram.observe(Duration::from_millis(300), true);
+        histogram.observe(Duration::from_secs(120), false);
+
+        assert_eq!(histogram.buckets, [1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2]);
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.failed, 1);
+    }
+
+    #[test]
+    fn target() {
+        assert_eq!(
+            request_target("GET /metrics HTTP/1.1\r\n"),
+            Some("/metrics")
+        );
+        assert_eq!(
+            request_target("GET /metrics?x=1 HTTP/1.1\r\n"),
+            Some("/metrics")
+        );
+        assert_eq!(request_target("POST /metrics HTTP/1.1\r\n"), None);
+        assert_eq!(request_target(""), None);
+    }
+
+    #[test]
+    fn render() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("must build runtime");
+        let metrics = LiveMetrics::new(None);
+
+        let text = runtime.block_on(async {
+            metrics
+                .observe("get_sbom[sha256:…]", Duration::from_millis(20), false)
+                .await;
+            metrics.record_status("/api/v3/sbom/{id}", 503).await;
+            metrics.record_status("/api/v3/sbom/{id}", 200).await;
+            metrics.record_status("/api/v3/sbom/{id}", 0).await;
+            metrics.render().await
+        });
+
+        assert!(text.contains(
+            "loadtest_transaction_latency_seconds_bucket{transaction=\"get_sbom[sha256:…]\",le=\"0.025\"} 1\n"
+        ));
+        assert!(text.contains(
+            "loadtest_transaction_latency_seconds_bucket{transaction=\"get_sbom[sha256:…]\",le=\"0.01\"} 0\n"
+        ));
+        assert!(text.contains(
+            "loadtest_request_errors_total{request=\"/api/v3/sbom/{id}\",status=\"503\"} 1\n"
+        ));
+        assert!(text.contains(
+            "loadtest_request_errors_total{request=\"/api/v3/sbom/{id}\",status=\"0\"} 1\n"
+        ));
+        assert!(!text.contains("status=\"200\""));
+        assert!(text.contains("loadtest_users 0\n"));
+        assert!(!text.contains("loadtest_oidc_tokens"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
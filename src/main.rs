@@ -1,51 +1,71 @@
 // The simplest loadtest example
+mod assertion;
+mod baseline;
+mod cli;
+#[cfg(feature = "postgres")]
 mod db;
+mod export;
+mod generator;
+mod labels;
+mod live;
+mod monitor;
 mod oidc;
+mod pacing;
+mod profile;
 mod restapi;
 mod scenario;
+mod server_metrics;
+mod statements;
+mod stats;
+mod thresholds;
+mod trace;
 mod utils;
+mod warmup;
 mod website;
+mod workload;
 
 use crate::{
-    oidc::{OpenIdTokenProvider, OpenIdTokenProviderConfigArguments},
+    assertion::Assertions,
+    cli::{Cli, Command},
+    oidc::OpenIdTokenProvider,
     restapi::{
-        advisory::*, analysis::*, misc::*, purl::*, sbom::*, sbom_group::*, vulnerability::*,
+        advisory::*, analysis::*, journey::*, misc::*, purl::*, request, sbom::*, sbom_group::*,
+        vulnerability::*,
     },
+    scenario::Select,
     website::*,
 };
-use anyhow::Context;
-use goose::prelude::*;
-use std::{str::FromStr, sync::Arc, time::Duration};
+use anyhow::{Context, anyhow, bail};
+use clap::Parser;
+use goose::{config::GooseConfiguration, prelude::*};
+use gumdrop::Options;
+use std::{sync::Arc, time::Duration};
 
 const MAX_ID_DISPLAY: usize = 32;
 
 /// Creates a named goose [`Transaction`] that issues a single `GET` request.
 ///
-/// If `query` is non-empty the URL becomes `path?query`, otherwise just `path`.
-/// The transaction's metric name is set to the resulting URL, giving every
-/// variant its own stable row in the load-test report.
+/// The transaction's metric name is set to the URL, giving every variant its
+/// own stable row in the load-test report.
 ///
 /// # Arguments
-/// * `path`  — The URL path, e.g. `"/api/v3/advisory"`.
-/// * `query` — The pre-encoded query string without the leading `?`,
-///   e.g. `"q=title~openssl"`, or `""` for no query string.
-fn list_tx(path: &'static str, query: &'static str) -> Transaction {
-    let url = if query.is_empty() {
-        path.to_string()
-    } else {
-        format!("{}?{}", path, query)
-    };
+/// * `url` — The URL path with an optional, pre-encoded query string,
+///   e.g. `"/api/v3/advisory?q=title~openssl"`.
+/// * `assertions` — The expectations on the response, failing the request if not met.
+fn list_tx(url: &str, assertions: Assertions) -> Transaction {
+    let assertions = Arc::new(assertions);
     Transaction::new(Arc::new({
-        let url = url.clone();
+        let url = url.to_string();
         move |user| {
             let url = url.clone();
+            let assertions = assertions.clone();
             Box::pin(async move {
-                let _response = user.get(&url).await?;
-                Ok(())
+                let response = request::get(user, &url).await?;
+                assertion::verify(user, response, &assertions).await
             })
         }
     }))
-    .set_name(&url)
+    .set_name(url)
 }
 
 /// Define a transaction and use its function identifier as name
@@ -66,7 +86,10 @@ macro_rules! tx {
         let display = $display;
         Transaction::new(Arc::new({
             let v1 = v1.clone();
-            move |s| Box::pin($n(v1.clone(), s))
+            move |s| {
+                let v1 = Select::select(&v1, s);
+                Box::pin($n(v1, s))
+            }
         }))
         .set_name(&display)
     }};
@@ -120,46 +143,403 @@ macro_rules! tx {
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    let wait_time_from: u64 = std::env::var("WAIT_TIME_FROM")
-        .map(|s| s.parse().unwrap_or(5))
-        .unwrap_or(5);
-    let wait_time_to: u64 = std::env::var("WAIT_TIME_TO")
-        .map(|s| s.parse().unwrap_or(15))
-        .unwrap_or(15);
-
-    let scenario_file = std::env::var("SCENARIO_FILE").ok();
-
-    if !matches!(
-        std::env::var("GENERATE_SCENARIO").ok().as_deref(),
-        None | Some("false" | "0")
-    ) {
-        let scenario = scenario::Scenario::eval().await?;
-        println!("{}", serde_json::to_string_pretty(&scenario)?);
+    match Cli::parse().command {
+        Command::Run(run) => run_loadtest(*run).await,
+        Command::GenerateScenario(generate) => generate_scenario(generate).await,
+        Command::ValidateScenario(validate) => validate_scenario(validate).await,
+        Command::GenerateSbom(config) => generate_sbom(config),
+        Command::GenerateAdvisory(generate) => generate_advisory(generate),
+        Command::Cleanup(cleanup) => cleanup_labels(cleanup).await,
+    }
+}
+
+async fn generate_scenario(generate: cli::GenerateScenario) -> anyhow::Result<()> {
+    let scenario = generate.eval().await?;
+    println!("{}", serde_json::to_string_pretty(&scenario)?);
+    Ok(())
+}
+
+fn generate_sbom(config: generator::sbom::SbomConfig) -> anyhow::Result<()> {
+    let sbom = config.generate(&mut rand::rng());
+    println!("{}", serde_json::to_string_pretty(&sbom)?);
+    Ok(())
+}
+
+fn generate_advisory(generate: cli::GenerateAdvisory) -> anyhow::Result<()> {
+    let purls = match &generate.scenario_file {
+        Some(scenario_file) => scenario::Scenario::load(scenario_file)?.purls(),
+        None => vec![],
+    };
+    let generator = generator::advisory::AdvisoryGenerator::new(generate.config, &purls);
+    let advisory = generator.generate(&mut rand::rng());
+    println!("{}", serde_json::to_string_pretty(&advisory)?);
+    Ok(())
+}
+
+async fn cleanup_labels(cleanup: cli::Cleanup) -> anyhow::Result<()> {
+    let provider = cleanup.auth.provider().await?;
+    let client = labels::LabelClient::new(cleanup.host, provider);
+    let restored = labels::restore(&client, cleanup.label_snapshot_file.as_ref()).await?;
+    println!("Restored the labels of {restored} entities");
+    Ok(())
+}
+
+/// Record the labels modified by the label users of the workload, if there are any.
+async fn snapshot_labels(
+    workload: &workload::Workload,
+    scenario: &scenario::Scenario,
+    host: &str,
+    provider: Option<OpenIdTokenProvider>,
+    file: &str,
+) -> anyhow::Result<Option<labels::LabelSnapshot>> {
+    let has_user = |name: &str| workload.users.iter().any(|user| user.name == name);
+    if !has_user("RestSBOMLabelUser") && !has_user("RestAdvisoryLableUser") {
+        return Ok(None);
+    }
+
+    let snapshot =
+        labels::LabelSnapshot::new(labels::LabelClient::new(host.to_string(), provider), file)?;
+
+    // advisories are picked at random during the run, and get recorded before being modified
+    if has_user("RestSBOMLabelUser") {
+        for id in scenario
+            .get_sbom_license_export
+            .iter()
+            .flat_map(|pool| pool.values())
+        {
+            snapshot.record(labels::Kind::Sbom, id).await?;
+        }
+    }
+
+    Ok(Some(snapshot))
+}
+
+async fn validate_scenario(validate: cli::ValidateScenario) -> anyhow::Result<()> {
+    let scenario = scenario::Scenario::load(&validate.scenario_file)?;
+
+    let serde_json::Value::Object(mut entries) = serde_json::to_value(&scenario)? else {
+        return Err(anyhow!("scenario must serialize into an object"));
+    };
+    let disabled: Vec<_> = entries
+        .iter()
+        .filter(|(_, value)| value.is_null())
+        .map(|(key, _)| key.as_str())
+        .collect();
+
+    println!(
+        "{}: {} of {} entries enabled",
+        validate.scenario_file,
+        entries.len() - disabled.len(),
+        entries.len()
+    );
+    for key in disabled {
+        println!("  disabled: {key}");
+    }
+
+    let Some(host) = validate.host else {
+        return Ok(());
+    };
+
+    let provider = validate.auth.provider().await?;
+    let validator =
+        scenario::validate::Validator::new(host, provider, validate.slow_threshold.into());
+    let checks = validator.validate(&entries).await;
+    println!();
+    scenario::validate::print_table(&checks);
+
+    if validate.rewrite {
+        let removed = scenario::validate::remove_missing(&mut entries, &checks);
+        let scenario: scenario::Scenario = serde_json::from_value(entries.into())
+            .context("rewritten scenario must still be valid")?;
+        std::fs::write(
+            &validate.scenario_file,
+            serde_json::to_string_pretty(&scenario)?,
+        )
+        .with_context(|| format!("writing scenario file: {}", validate.scenario_file))?;
+        println!(
+            "\nRemoved {removed} missing values from {}",
+            validate.scenario_file
+        );
+    }
+
+    Ok(())
+}
+
+async fn run_loadtest(run: cli::Run) -> anyhow::Result<()> {
+    let configuration = GooseConfiguration::parse_args_default(&run.goose)
+        .map_err(|err| anyhow!("invalid goose option: {err}"))?;
+    if configuration.help {
+        println!("{}", GooseConfiguration::usage());
         return Ok(());
     }
 
-    let scenario = Arc::new(scenario::Scenario::load(scenario_file.as_deref()).await?);
+    let scenario = Arc::new(run.scenario.load().await?);
+    let workload = workload::Workload::load(run.workload_file.as_deref())?;
+    let thresholds = run
+        .thresholds_file
+        .as_deref()
+        .map(thresholds::Thresholds::load)
+        .transpose()?;
+    let baseline = run
+        .baseline
+        .as_deref()
+        .map(baseline::RunResult::load)
+        .transpose()?;
+    let profile = run
+        .load_profile
+        .as_deref()
+        .map(profile::LoadProfile::load)
+        .transpose()?;
+    if profile
+        .as_ref()
+        .is_some_and(|profile| profile.capacity_search)
+        && thresholds.is_none()
+    {
+        bail!("a capacity search requires a thresholds file, defining the sustainable load");
+    }
 
-    let custom_client = if matches!(
-        std::env::var("AUTH_DISABLED").ok().as_deref(),
-        Some("true" | "1")
-    ) {
-        None
-    } else {
-        let provider = create_oidc_provider().await?;
-        Some(Transaction::new(Arc::new(move |user| {
+    let timeout: Duration = run.request_timeout.into();
+    let provider = run.auth.provider().await?;
+    let custom_client = provider.clone().map(|provider| {
+        Transaction::new(Arc::new(move |user| {
             let provider = provider.clone();
-            Box::pin(async move { setup_custom_client(&provider, user).await })
-        })))
+            Box::pin(async move { setup_custom_client(&provider, timeout, user).await })
+        }))
+    });
+
+    // Used for the advisory label user, which needs to know the host upfront.
+    let host = Some(configuration.host.clone())
+        .filter(|host| !host.is_empty())
+        .or_else(|| std::env::var("HOST").ok())
+        .unwrap_or_else(|| "http://localhost:8080".to_string());
+
+    let labels = snapshot_labels(
+        &workload,
+        &scenario,
+        &host,
+        provider.clone(),
+        &run.label_snapshot_file,
+    )
+    .await?
+    .map(Arc::new);
+
+    // the analysis users are the ones affected by the state of the graph cache
+    let analysis = workload
+        .users
+        .iter()
+        .any(|user| matches!(user.name.as_str(), "AnalysisUser" | "GraphWalkUser"))
+        .then(|| monitor::AnalysisClient::new(host.clone(), provider.clone()));
+    if let Some(analysis) = &analysis {
+        let names = scenario
+            .analysis_component_names()
+            .map(|names| names.values().to_vec())
+            .unwrap_or_default();
+        monitor::gate(
+            analysis,
+            run.analysis_cache,
+            &names,
+            run.analysis_status_interval.into(),
+            run.analysis_warmup_timeout.into(),
+        )
+        .await?;
+    }
+
+    let builtin = Builtin {
+        scenario,
+        strategy: run.scenario.pool_strategy,
+        host,
+        // Create atomic counter for sequential delete strategy
+        delete_counter: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+        labels,
+    };
+
+    let live_metrics = run
+        .metrics_listen
+        .map(|_| Arc::new(live::LiveMetrics::new(provider.clone())));
+    let live_server = match (&live_metrics, run.metrics_listen) {
+        (Some(live_metrics), Some(address)) => {
+            Some(live::serve(live_metrics.clone(), address).await?)
+        }
+        _ => None,
     };
 
-    // Create atomic counter for sequential delete strategy
-    let delete_counter = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let status_monitor = analysis.map(|analysis| {
+        monitor::StatusMonitor::start(analysis, run.analysis_status_interval.into())
+    });
+    #[cfg(feature = "postgres")]
+    let observer = match (run.pg_stat_statements, &run.scenario.database_url) {
+        (Some(mode), Some(database_url)) => {
+            Some(statements::Observer::start(database_url.clone(), mode).await?)
+        }
+        _ => None,
+    };
+    let sampler = run
+        .server_metrics
+        .server_metrics_url
+        .clone()
+        .map(|url| server_metrics::Sampler::start(&run.server_metrics, url));
+    // the configuration gets consumed by the attack, which writes the reports
+    let report_files = configuration.report_file.clone();
+
+    let attack = Attack {
+        run: &run,
+        workload: &workload,
+        builtin: &builtin,
+        custom_client: &custom_client,
+        live_metrics: live_metrics.as_ref(),
+        #[cfg(feature = "postgres")]
+        observer: observer.as_ref(),
+    };
+    let attacks = match &profile {
+        Some(profile) => run_profile(profile, &attack, thresholds.as_ref()).await,
+        None => attack
+            .execute(configuration, 1.0)
+            .await
+            .map(|outcome| (outcome, vec![])),
+    };
+
+    // once after all attacks, as the SBOM labels are only recorded before the first one
+    if let Some(labels) = &builtin.labels {
+        match labels.restore().await {
+            Ok(restored) => log::info!("Restored the labels of {restored} entities"),
+            Err(err) => log::error!(
+                "Unable to restore labels, use the `cleanup` command to try again: {err:#}"
+            ),
+        }
+    }
+    let (outcome, stages) = attacks?;
+
+    if let Some(live_server) = live_server {
+        live_server.abort();
+    }
+
+    let analysis_status = match status_monitor {
+        Some(status_monitor) => {
+            let samples = status_monitor.stop().await;
+            println!("\n=== ANALYSIS STATUS ===");
+            monitor::print_summary(&samples);
+            server_metrics::embed(&report_files, &monitor::html(&samples))?;
+            samples
+        }
+        None => vec![],
+    };
+
+    let server_metrics = match sampler {
+        Some(sampler) => {
+            let series = sampler.stop().await;
+            println!("\n=== SERVER METRICS ===");
+            server_metrics::print_table(&series);
+            server_metrics::embed(&report_files, &server_metrics::html(&series))?;
+            series
+        }
+        None => vec![],
+    };
+
+    #[cfg(feature = "postgres")]
+    let statements = match observer {
+        Some(observer) => match observer.stop(run.pg_stat_statements_top).await {
+            Ok(statements) => {
+                println!("\n=== SQL STATEMENTS ===");
+                statements::print_tables(&statements, run.pg_stat_statements_top);
+                statements
+            }
+            Err(err) => {
+                log::warn!("Unable to observe the SQL statements: {err:#}");
+                vec![]
+            }
+        },
+        None => vec![],
+    };
+
+    if let Some(provider) = &provider {
+        let metrics = provider.metrics();
+        println!("\n=== OIDC TOKENS ===\n{metrics}");
+        if metrics.failed > 0 {
+            log::warn!("{} token requests failed", metrics.failed);
+        }
+    }
+
+    let mut result = baseline::RunResult::new(outcome.duration, &outcome.stats);
+    result.latency = outcome.latency;
+    result.warmup = outcome.warmup;
+    result.analysis_status = analysis_status;
+    result.stages = stages;
+    result.server_metrics = server_metrics;
+    #[cfg(feature = "postgres")]
+    {
+        result.statements = statements;
+    }
+    if let Some(result_file) = &run.result_file {
+        result.store(result_file)?;
+    }
+    export::Export::new(
+        export::Labels::new(
+            run.scenario.scenario_file.as_deref(),
+            run.export.dataset.clone(),
+        ),
+        outcome.duration,
+        &outcome.stats,
+    )
+    .store(&run.export)?;
+
+    if let Some(baseline) = baseline {
+        let comparisons = result.compare(&baseline, run.baseline_tolerance);
+        println!("\n=== BASELINE ===");
+        baseline::print_table(&comparisons);
+    }
+
+    if let Some(thresholds) = thresholds {
+        // finding the stage violating the thresholds is the goal of a capacity search
+        if profile.is_some_and(|profile| profile.capacity_search) {
+            return Ok(());
+        }
+
+        let evaluations = thresholds.evaluate(&outcome.stats);
+        println!("\n=== THRESHOLDS ===");
+        thresholds::print_table(&evaluations);
+
+        let violations = evaluations.iter().filter(|e| !e.passed).count();
+        if violations > 0 {
+            bail!("{violations} of {} thresholds violated", evaluations.len());
+        }
+    }
+
+    Ok(())
+}
+
+/// The outcome of a single goose attack.
+struct Outcome {
+    /// The duration, in seconds
+    duration: usize,
+    stats: Vec<stats::TransactionStats>,
+    /// The latencies of the transactions of paced users, measured from their scheduled starts
+    latency: Vec<baseline::TransactionSummary>,
+    /// The transactions of the warm-up, which are not part of the stats
+    warmup: Vec<baseline::TransactionSummary>,
+}
 
-    GooseAttack::initialize()?
-        .test_start(
+/// Everything required for running goose attacks of the workload, shared by the stages of a load profile.
+struct Attack<'a> {
+    run: &'a cli::Run,
+    workload: &'a workload::Workload,
+    builtin: &'a Builtin,
+    custom_client: &'a Option<Transaction>,
+    live_metrics: Option<&'a Arc<live::LiveMetrics>>,
+    /// Observes the SQL statements of every stage of a load profile
+    #[cfg(feature = "postgres")]
+    observer: Option<&'a statements::Observer>,
+}
+
+impl Attack<'_> {
+    /// Run a goose attack of the workload, scaling the rates of the paced users by `rate_scale`.
+    async fn execute(
+        &self,
+        configuration: GooseConfiguration,
+        rate_scale: f64,
+    ) -> anyhow::Result<Outcome> {
+        let mut attack = GooseAttack::initialize_with_config(configuration)?.test_start(
             Transaction::new(Arc::new({
-                let scenario = scenario.clone();
+                let scenario = self.builtin.scenario.clone();
                 move |_| {
                     let scenario = scenario.clone();
                     Box::pin(async move {
@@ -169,273 +549,355 @@ async fn main() -> Result<(), anyhow::Error> {
                 }
             }))
             .set_name("log scenario"),
-        )
-        .register_scenario({
-            create_scenario(
-                "WebsiteUser",
-                wait_time_from,
-                wait_time_to,
-                custom_client.clone(),
-            )?
-            .register_transaction(tx!(website_index))
-            .register_transaction(tx!(website_openapi))
-            .register_transaction(tx!(website_sboms))
-            .register_transaction(tx!(website_packages))
-            .register_transaction(tx!(website_advisories))
-            .register_transaction(tx!(website_importers))
-        })
-        .register_scenario({
-            let mut s = create_scenario(
-                "RestAPIUser",
-                wait_time_from,
-                wait_time_to,
-                custom_client.clone(),
-            )?
-            .set_weight(5)?
-            .register_transaction(list_tx("/api/v3/organization", ""))
-            .register_transaction(list_tx("/api/v3/advisory", ""))
-            .register_transaction(list_tx("/api/v3/advisory", "offset=100&limit=10"))
-            .register_transaction(list_tx("/api/v3/advisory", "q=identifier%3dCVE-2022-0981"))
-            .register_transaction(list_tx("/api/v3/advisory", "q=CVE-2021-"))
-            .register_transaction(list_tx("/api/v3/vulnerability", ""))
-            .register_transaction(list_tx("/api/v3/vulnerability", "offset=100&limit=10"))
-            .register_transaction(list_tx("/api/v3/importer", ""))
-            .register_transaction(list_tx("/api/v3/purl", ""))
-            .register_transaction(list_tx("/api/v3/purl", "offset=100&limit=10"))
-            .register_transaction(list_tx("/api/v3/purl", "q=curl"))
-            .register_transaction(list_tx("/api/v3/purl", "q=name=curl"))
-            .register_transaction(list_tx("/api/v3/product", ""))
-            .register_transaction(list_tx("/api/v3/sbom", ""))
-            .register_transaction(list_tx("/api/v3/sbom", ""))
-            .register_transaction(list_tx("/api/v3/sbom", "offset=100&limit=10"))
-            .register_transaction(list_tx("/api/v3/sbom", "offset=100&limit=10"))
-            .register_transaction(tx!(list_advisory_labels))
-            .register_transaction(list_tx("/api/v3/sbom-labels", ""))
-            .register_transaction(list_tx("/api/v3/purl/base", ""))
-            .register_transaction(list_tx("/api/v3/license", ""))
-            .register_transaction(list_tx("/api/v3/license/spdx/license", ""))
-            .register_transaction(list_tx("/api/v3/weakness", ""))
-            .register_transaction(list_tx("/api/v3/group/sbom", ""))
-            .register_transaction(tx!(post_vulnerability_analyze_v3))
-            .register_transaction(list_tx("/.well-known/trustify", ""))
-            .register_transaction(tx!(post_extract_sbom_purls))
-            .register_transaction(list_tx("/api/v3/advisory", "q=title~openssl"))
-            .register_transaction(list_tx("/api/v3/advisory", "q=modified>3 days ago"))
-            .register_transaction(list_tx("/api/v3/advisory", "sort=modified:desc"))
-            .register_transaction(list_tx("/api/v3/advisory", "deprecated=Consider"))
-            .register_transaction(list_tx("/api/v3/sbom", "q=name~redhat"))
-            .register_transaction(list_tx("/api/v3/sbom", "q=published>2024-01-01"))
-            .register_transaction(list_tx("/api/v3/sbom", "sort=ingested:desc"))
-            .register_transaction(list_tx("/api/v3/sbom", "q=label:type=product"))
-            .register_transaction(list_tx("/api/v3/vulnerability", "q=base_severity=high"))
-            .register_transaction(list_tx("/api/v3/vulnerability", "q=base_score>=7.0"))
-            .register_transaction(list_tx("/api/v3/vulnerability", "q=cwes=CWE-79"))
-            .register_transaction(list_tx("/api/v3/vulnerability", "sort=base_score:desc"))
-            .register_transaction(list_tx("/api/v3/purl", "q=purl:ty=rpm"))
-            .register_transaction(list_tx("/api/v3/purl", "q=purl:namespace=redhat"))
-            .register_transaction(list_tx("/api/v3/purl", "sort=purl:name:asc"))
-            .register_transaction(list_tx("/api/v3/purl/base", "q=type=rpm"))
-            .register_transaction(list_tx("/api/v3/purl/base", "q=namespace=redhat"))
-            .register_transaction(list_tx("/api/v3/purl/base", "sort=name:asc"))
-            .register_transaction(list_tx("/api/v3/organization", "sort=name:asc"))
-            .register_transaction(list_tx("/api/v3/product", "q=name~openshift"))
-            .register_transaction(list_tx("/api/v3/product", "sort=name:asc"))
-            .register_transaction(list_tx("/api/v3/weakness", "q=description~injection"))
-            .register_transaction(list_tx("/api/v3/weakness", "sort=id:asc"))
-            .register_transaction(list_tx("/api/v3/group/sbom", "totals=true"))
-            .register_transaction(list_tx("/api/v3/group/sbom", "parents=resolve"));
-
-            tx!(s.get_sbom?(scenario.get_sbom.clone()));
-            tx!(s.get_sbom_advisories?(scenario.get_sbom_advisories.clone()));
-            tx!(s.get_sbom_packages?(scenario.get_sbom_packages.clone()));
-            tx!(s.get_sbom_related?(scenario.get_sbom_related.clone()));
-            tx!(s.get_vulnerability?(scenario.get_vulnerability.clone()));
-            tx!(s.get_vulnerability_scores?(scenario.get_vulnerability.clone()));
-            tx!(s.sbom_by_package?(scenario.sbom_by_package.clone()));
-            tx!(s.get_sbom_license_ids?(scenario.sbom_license_ids.clone()));
-            tx!(s.post_vulnerability_analyze?(scenario.analyze_purl.clone()));
-            tx!(s.get_purl_details?(scenario.get_purl_details.clone()));
-            // Register recommend transactions with different batch sizes for concurrency testing
-            if let Some(purls) = scenario.get_recommendations.clone() {
-                for batch_size in [25, 50, 128] {
-                    let actual_size = batch_size.min(purls.0.len());
-                    s = s.register_transaction(
-                        tx!(get_recommendations(purls.clone(), actual_size),
-                            name: &format!("get_recommendations[batch={}]", actual_size)),
-                    );
-                }
+        );
+
+        let warmup = Arc::new(warmup::Warmup::new(self.run.warmup()));
+        let mut pacing = pacing::Pacing::new(warmup.clone());
+        for user in &self.workload.users {
+            // paced users start their transactions on schedule, instead of waiting between them
+            let wait_time = match user.rate {
+                Some(_) => workload::WaitTime { from: 0, to: 0 },
+                None => user.wait_time.unwrap_or(workload::WaitTime {
+                    from: self.run.wait_time_from,
+                    to: self.run.wait_time_to,
+                }),
+            };
+            let mut s = create_scenario(&user.name, wait_time, self.custom_client.clone())?
+                .set_weight(user.weight)?;
+            for request in &user.list {
+                s = s.register_transaction(list_tx(request.url(), request.assertions()));
+            }
+            s = self.builtin.register(user, s).await;
+            s = trace::trace(s, self.run.slow_request_threshold.map(Into::into));
+            if let Some(live_metrics) = self.live_metrics {
+                s = live_metrics.measure(s);
             }
+            s = match user.rate {
+                Some(rate) => pacing.pace(&user.name, rate * rate_scale, s),
+                None => warmup.measure(s),
+            };
+            attack = attack.register_scenario(s);
+        }
 
-            tx!(s.download_advisory?(scenario.download_advisory.clone()));
-            tx!(s.get_advisory?(scenario.get_advisory.clone()));
-
-            tx!(s.download_sbom?(scenario.download_sbom.clone()));
-            tx!(s.get_sbom_license_export?(
-                scenario.get_sbom_license_export.clone()
-            ));
-            tx!(s.count_sbom_by_package?(
-                scenario.count_sbom_by_package.clone()
-            ));
-            tx!(s.get_sbom_group?(scenario.get_sbom_group.clone()));
-            tx!(s.get_sbom_group_assignments?(
-                scenario.get_sbom_group.clone()
-            ));
-            tx!(s.get_product?(scenario.get_product.clone()));
-            tx!(s.get_organization?(scenario.get_organization.clone()));
-            tx!(s.get_base_purl?(scenario.get_base_purl.clone()));
-            tx!(s.get_importer?(scenario.get_importer.clone()));
-            tx!(s.get_importer_report?(scenario.get_importer.clone()));
-            tx!(s.get_weakness?(scenario.get_weakness.clone()));
-            tx!(s.get_spdx_license?(scenario.get_spdx_license.clone()));
-            s
-        })
-        .register_scenario({
-            create_scenario(
-                "RestAPIUserSlow",
-                wait_time_from,
-                wait_time_to,
-                custom_client.clone(),
-            )?
-            .set_weight(1)?
-            .register_transaction(list_tx("/api/v3/license", "q=ASL&sort=license:desc"))
-            .register_transaction(list_tx("/api/v3/sbom", "q=license~GPL&sort=name:desc"))
-            .register_transaction(list_tx("/api/v3/purl", "q=license~GPLv3+ with exceptions|Apache&sort=name:desc"))
-            .register_transaction(list_tx("/api/v3/license", "q=license~Apache"))
-            .register_transaction(list_tx("/api/v3/license", "q=license~GPL"))
-            .register_transaction(list_tx("/api/v3/license/spdx/license", "q=apache"))
-            .register_transaction(list_tx("/api/v3/license/spdx/license", "q=gpl"))
-        })
-        .register_scenario({
-            let mut s = create_scenario(
-                "AnalysisUser",
-                wait_time_from,
-                wait_time_to,
-                custom_client.clone(),
-            )?
-            .set_weight(2)?
-            .register_transaction(list_tx("/api/v3/analysis/status", ""))
-            .register_transaction(list_tx("/api/v3/analysis/latest/component/cpe%3A%2Fa%3Aredhat%3Aopenshift_builds%3A1.3%3A%3Ael9", ""))
-            // TODO: .register_transaction(tx!(search_analysis_component))
-            // TODO: .register_transaction(tx!(search_latest_component))
-            // TODO: .register_transaction(search_tx("/api/v3/analysis/component","q=openssl&descendants=1"))
-            // TODO: .register_transaction(search_tx("/api/v3/analysis/component", "q=curl&relationships=contains,dependency"))
-            ;
-
-            tx!(s.get_analysis_component?(
-                scenario.get_analysis_component.clone()
-            ));
-            tx!(s.render_sbom_graph_dot?(scenario.render_sbom_graph.clone()));
-
-            s
+        if let Some(live_metrics) = self.live_metrics {
+            live_metrics.reset_users();
+        }
+        let metrics = attack.execute().await?;
+
+        let mut duration = metrics.duration;
+        let mut stats = stats::TransactionStats::from_metrics(&metrics);
+        let mut latency = vec![];
+        let mut excluded = vec![];
+        if warmup.is_enabled() {
+            duration = warmup.steady_duration().min(metrics.duration);
+            let warmup_stats = warmup.exclude(&mut stats).await;
+            excluded = baseline::summaries(warmup.warmup_duration(), &warmup_stats);
+            println!("\n=== WARM-UP ===");
+            warmup.print_table().await;
+        }
+        if !pacing.is_empty() {
+            pacing.service_times(&mut stats).await;
+            latency = baseline::summaries(duration, &pacing.latencies().await);
+            println!("\n=== ARRIVAL RATE ===");
+            pacing.print_table(metrics.duration).await;
+        }
+
+        Ok(Outcome {
+            duration,
+            stats,
+            latency,
+            warmup: excluded,
         })
-        .register_scenario({
-            let mut s = create_scenario(
-                "RestAPIUserDelete",
-                wait_time_from,
-                wait_time_to,
-                custom_client.clone(),
-            )?
-            .set_weight(1)?
-            // With 100 SBOM IDs this ensure they all delete something in the sequential situation
-            .set_wait_time(Duration::from_secs(3), Duration::from_secs(4))?;
-            // Register delete transaction if pool is available
-            if let Some(pool) = scenario.delete_sbom_pool.clone() {
-                tx!(s.delete_sbom_from_pool_sequential?(
-                    scenario.delete_sbom_pool.clone(),
-                    delete_counter.clone()
-                ),
-                name: format! ("delete_sbom_from_pool_sequential[{} SBOMs]", pool.len()))
+    }
+}
+
+/// Run the stages of a load profile, returning the outcome of the last stage and the results of all stages.
+///
+/// A capacity search stops at the first stage violating the thresholds.
+async fn run_profile(
+    profile: &profile::LoadProfile,
+    attack: &Attack<'_>,
+    thresholds: Option<&thresholds::Thresholds>,
+) -> anyhow::Result<(Outcome, Vec<profile::StageResult>)> {
+    let mut last = None;
+    let mut stages = vec![];
+
+    for (index, stage) in profile.stages.iter().enumerate() {
+        log::info!("Stage {}/{}: {stage}", index + 1, profile.stages.len());
+
+        // the options of the stage come last, overriding the ones of the command line
+        let args = [attack.run.goose.clone(), stage.goose_args()].concat();
+        let configuration = GooseConfiguration::parse_args_default(&args)
+            .map_err(|err| anyhow!("invalid goose option: {err}"))?;
+
+        let outcome = attack.execute(configuration, stage.rate_scale).await?;
+
+        let passed = thresholds.map(|thresholds| {
+            thresholds
+                .evaluate(&outcome.stats)
+                .iter()
+                .all(|evaluation| evaluation.passed)
+        });
+        let mut result = profile::StageResult::new(stage, outcome.duration, &outcome.stats, passed);
+        #[cfg(feature = "postgres")]
+        if let Some(observer) = attack.observer {
+            match observer.window(attack.run.pg_stat_statements_top).await {
+                Ok(statements) => result.statements = statements,
+                Err(err) => {
+                    log::warn!("Unable to observe the SQL statements of the stage: {err:#}")
+                }
             }
-            s
-        })
-        .register_scenario({
-            let mut s = create_scenario(
-                "RestSBOMLabelUser",
-                wait_time_from,
-                wait_time_to,
-                custom_client.clone(),
-            )?
-            .set_weight(2)?;
-            tx!(s.put_sbom_labels?(scenario.get_sbom_license_export.clone()));
-            tx!(s.patch_sbom_labels?(
-                scenario.get_sbom_license_export.clone()
-            ));
-            s
-        })
-        .register_scenario({
-            let mut s = create_scenario(
-                "RestAdvisoryLableUser",
-                wait_time_from,
-                wait_time_to,
-                custom_client,
-            )?
-            .set_weight(5)?;
-            // Register advisory label transactions if host is available.
-            // Since the scenario object doesn't provide host information, we use environment
-            let host = s
-                .host
-                .clone()
-                .or_else(|| std::env::var("HOST").ok())
-                .unwrap_or_else(|| "http://localhost:8080".to_string());
-            let total_advisories = get_advisory_total(host).await.ok();
-            if let Some(total) = total_advisories {
-                tx!(s.find_random_advisory?(Some(total)));
-                s = s.register_transaction(tx!(put_advisory_labels));
-                s = s.register_transaction(tx!(patch_advisory_labels));
+        }
+        stages.push(result);
+        last = Some(outcome);
+
+        if profile.capacity_search && passed == Some(false) {
+            break;
+        }
+    }
+
+    println!("\n=== LOAD PROFILE ===");
+    profile::print_table(&stages);
+    if profile.capacity_search {
+        match profile::capacity(&stages) {
+            Some((index, stage)) => println!(
+                "\nMaximum sustainable throughput: {:.2}/s, reached in stage {}",
+                stage.throughput,
+                index + 1
+            ),
+            None => println!("\nNo stage met the thresholds"),
+        }
+    }
+
+    let last = last.ok_or_else(|| anyhow!("the load profile has no stages"))?;
+    Ok((last, stages))
+}
+
+/// State required for registering the built-in transactions of the known users.
+struct Builtin {
+    scenario: Arc<scenario::Scenario>,
+    strategy: scenario::Strategy,
+    host: String,
+    delete_counter: Arc<std::sync::atomic::AtomicUsize>,
+    /// The snapshot of the labels modified by the label users
+    labels: Option<Arc<labels::LabelSnapshot>>,
+}
+
+impl Builtin {
+    /// Select values of a scenario entry using the configured strategy.
+    fn pool(&self, pool: &Option<scenario::Pool>) -> Option<scenario::Selector> {
+        pool.as_ref().map(|pool| pool.selector(self.strategy))
+    }
+
+    /// Register the transactions of a known user. Unknown users are returned unchanged.
+    async fn register(&self, user: &workload::User, mut s: Scenario) -> Scenario {
+        let scenario = &self.scenario;
+
+        match user.name.as_str() {
+            "WebsiteUser" => s
+                .register_transaction(tx!(website_index))
+                .register_transaction(tx!(website_openapi))
+                .register_transaction(tx!(website_sboms))
+                .register_transaction(tx!(website_packages))
+                .register_transaction(tx!(website_advisories))
+                .register_transaction(tx!(website_importers)),
+            "RestAPIUser" => {
+                s = s
+                    .register_transaction(tx!(list_advisory_labels))
+                    .register_transaction(tx!(post_vulnerability_analyze_v3))
+                    .register_transaction(tx!(post_extract_sbom_purls));
+
+                tx!(s.get_sbom?(self.pool(&scenario.get_sbom)));
+                tx!(s.get_sbom_advisories?(
+                    self.pool(&scenario.get_sbom_advisories)
+                ));
+                tx!(s.get_sbom_packages?(self.pool(&scenario.get_sbom_packages)));
+                tx!(s.get_sbom_related?(self.pool(&scenario.get_sbom_related)));
+                tx!(s.get_vulnerability?(self.pool(&scenario.get_vulnerability)));
+                tx!(s.get_vulnerability_scores?(
+                    self.pool(&scenario.get_vulnerability)
+                ));
+                tx!(s.sbom_by_package?(self.pool(&scenario.sbom_by_package)));
+                tx!(s.get_sbom_license_ids?(
+                    self.pool(&scenario.sbom_license_ids)
+                ));
+                tx!(s.post_vulnerability_analyze?(
+                    self.pool(&scenario.analyze_purl)
+                ));
+                tx!(s.get_purl_details?(self.pool(&scenario.get_purl_details)));
+                // Register recommend transactions with different batch sizes for concurrency testing
+                if let Some(purls) = scenario.get_recommendations.clone() {
+                    for batch_size in [25, 50, 128] {
+                        let actual_size = batch_size.min(purls.0.len());
+                        s = s.register_transaction(
+                            tx!(get_recommendations(purls.clone(), actual_size),
+                                name: &format!("get_recommendations[batch={}]", actual_size)),
+                        );
+                    }
+                }
+
+                tx!(s.download_advisory?(self.pool(&scenario.download_advisory)));
+                tx!(s.get_advisory?(self.pool(&scenario.get_advisory)));
+
+                tx!(s.download_sbom?(self.pool(&scenario.download_sbom)));
+                tx!(s.get_sbom_license_export?(
+                    self.pool(&scenario.get_sbom_license_export)
+                ));
+                tx!(s.count_sbom_by_package?(
+                    self.pool(&scenario.count_sbom_by_package)
+                ));
+                tx!(s.get_sbom_group?(self.pool(&scenario.get_sbom_group)));
+                tx!(s.get_sbom_group_assignments?(
+                    self.pool(&scenario.get_sbom_group)
+                ));
+                tx!(s.get_product?(self.pool(&scenario.get_product)));
+                tx!(s.get_organization?(self.pool(&scenario.get_organization)));
+                tx!(s.get_base_purl?(self.pool(&scenario.get_base_purl)));
+                tx!(s.get_importer?(self.pool(&scenario.get_importer)));
+                tx!(s.get_importer_report?(self.pool(&scenario.get_importer)));
+                tx!(s.get_weakness?(self.pool(&scenario.get_weakness)));
+                tx!(s.get_spdx_license?(self.pool(&scenario.get_spdx_license)));
+                s
             }
-            s
-        })
-        .execute()
-        .await?;
+            "AnalysisUser" => {
+                if let Some(names) = self.pool(&scenario.analysis_component_names()) {
+                    for params in COMPONENT_SEARCHES {
+                        s = s
+                            .register_transaction(tx!(
+                                search_analysis_component(names, params),
+                                name: &component_search_name("search_analysis_component", params)
+                            ))
+                            .register_transaction(tx!(
+                                search_latest_component(names, params),
+                                name: &component_search_name("search_latest_component", params)
+                            ));
+                    }
+                }
 
-    Ok(())
+                tx!(s.get_analysis_component?(
+                    self.pool(&scenario.get_analysis_component)
+                ));
+                tx!(s.render_sbom_graph_dot?(
+                    self.pool(&scenario.render_sbom_graph)
+                ));
+                s
+            }
+            "GraphWalkUser" => {
+                let config = Arc::new(user.graph_walk.clone().unwrap_or_default());
+                if let Some(names) = self.pool(&scenario.analysis_component_names()) {
+                    let name = format!(
+                        "walk_analysis_graph[{}:{} hops]",
+                        config.direction, config.hops
+                    );
+                    s = s.register_transaction(tx!(
+                        walk_analysis_graph(names, config),
+                        name: &name
+                    ));
+                }
+                s
+            }
+            "RestAPIUserDelete" => {
+                // copies of the pool entries, unless the SBOMs are configured explicitly
+                let pool = scenario
+                    .delete_sbom_pool
+                    .clone()
+                    .and_then(scenario::Pool::new);
+                let source = Arc::new(match (&user.sbom, pool) {
+                    (None, Some(pool)) => DisposableSbom::Copy(pool.selector(self.strategy)),
+                    (config, _) => DisposableSbom::Generated(config.clone().unwrap_or_default()),
+                });
+                s.register_transaction(tx!(ingest_and_delete_sbom(source.clone())))
+            }
+            "RestAPIUserDeletePool" => {
+                // permanently deletes the SBOMs of the pool
+                if let Some(pool) = scenario
+                    .delete_sbom_pool
+                    .clone()
+                    .filter(|pool| !pool.is_empty())
+                {
+                    let name = format!("delete_sbom_from_pool_sequential[{} SBOMs]", pool.len());
+                    s = s.register_transaction(tx!(
+                        delete_sbom_from_pool_sequential(pool, self.delete_counter.clone()),
+                        name: &name
+                    ));
+                }
+                s
+            }
+            "IngestUser" => {
+                let config = Arc::new(user.sbom.clone().unwrap_or_default());
+                let name = format!("upload_sbom[{}:{}]", config.format, config.packages);
+                s.register_transaction(tx!(upload_sbom(config), name: &name))
+            }
+            "AdvisoryIngestUser" => {
+                let generator = Arc::new(generator::advisory::AdvisoryGenerator::new(
+                    user.advisory.clone().unwrap_or_default(),
+                    &scenario.purls(),
+                ));
+                let name = format!("upload_advisory[{}]", generator.config().format);
+                s.register_transaction(tx!(upload_advisory(generator), name: &name))
+            }
+            "JourneyUser" => {
+                let config = Arc::new(user.journey.clone().unwrap_or_default());
+                s.register_transaction(tx!(sbom_journey(config), name: "sbom_journey"))
+                    .register_transaction(tx!(advisory_journey(config), name: "advisory_journey"))
+                    .register_transaction(tx!(
+                        vulnerability_journey(config),
+                        name: "vulnerability_journey"
+                    ))
+            }
+            "RestSBOMLabelUser" => {
+                tx!(s.put_sbom_labels?(
+                    self.pool(&scenario.get_sbom_license_export)
+                ));
+                tx!(s.patch_sbom_labels?(
+                    self.pool(&scenario.get_sbom_license_export)
+                ));
+                s
+            }
+            "RestAdvisoryLableUser" => {
+                // Register advisory label transactions if host is available.
+                // Since the scenario object doesn't provide host information, we use the goose configuration
+                let host = s.host.clone().unwrap_or_else(|| self.host.clone());
+                let total_advisories = get_advisory_total(host).await.ok();
+                if let (Some(total), Some(labels)) = (total_advisories, &self.labels) {
+                    tx!(s.find_random_advisory?(Some(total)));
+                    s = s.register_transaction(
+                        tx!(put_advisory_labels(labels.clone()), name: "put_advisory_labels"),
+                    );
+                    s = s.register_transaction(
+                        tx!(patch_advisory_labels(labels.clone()), name: "patch_advisory_labels"),
+                    );
+                }
+                s
+            }
+            _ => s,
+        }
+    }
 }
 
 fn create_scenario(
     name: &str,
-    wait_time_from: u64,
-    wait_time_to: u64,
+    wait_time: workload::WaitTime,
     custom_client: Option<Transaction>,
 ) -> Result<Scenario, GooseError> {
     let mut s = scenario!(name);
     if let Some(client) = custom_client {
-        s = s.register_transaction(client.set_name("logon"));
+        s = s.register_transaction(client.set_name("logon").set_on_start());
     }
     s.set_wait_time(
-        Duration::from_secs(wait_time_from),
-        Duration::from_secs(wait_time_to),
+        Duration::from_secs(wait_time.from),
+        Duration::from_secs(wait_time.to),
     )
 }
 
-async fn create_oidc_provider() -> anyhow::Result<OpenIdTokenProvider> {
-    let issuer_url = std::env::var("ISSUER_URL").context("Missing env-var 'ISSUER_URL'")?;
-    let client_id = std::env::var("CLIENT_ID").context("Missing env-var 'CLIENT_ID'")?;
-    let client_secret =
-        std::env::var("CLIENT_SECRET").context("Missing env-var 'CLIENT_SECRET'")?;
-    let refresh_before = std::env::var("OIDC_REFRESH_BEFORE").unwrap_or_else(|_| "30s".to_string());
-    let refresh_before =
-        humantime::Duration::from_str(&refresh_before).context("OIDC_REFRESH_BEFORE must parse")?;
-
-    let provider = OpenIdTokenProvider::with_config(OpenIdTokenProviderConfigArguments {
-        client_id,
-        client_secret,
-        issuer_url,
-        refresh_before,
-        tls_insecure: false,
-    })
-    .await
-    .context("discover OIDC client")?;
-
-    Ok(provider)
-}
-
 // required until https://github.com/tag1consulting/goose/pull/605 is merged
 #[allow(clippy::expect_used)]
 async fn setup_custom_client(
     provider: &OpenIdTokenProvider,
+    timeout: Duration,
     user: &mut GooseUser,
 ) -> TransactionResult {
-    set_custom_client(provider, user)
+    set_custom_client(provider, timeout, user)
         .await
         .expect("Failed to set up client");
     Ok(())
@@ -443,34 +905,18 @@ async fn setup_custom_client(
 
 async fn set_custom_client(
     provider: &OpenIdTokenProvider,
+    timeout: Duration,
     user: &mut GooseUser,
 ) -> anyhow::Result<()> {
-    use reqwest::header;
-
     log::debug!("Creating a new custom client");
 
-    let auth_token: String = provider
-        .provide_token()
-        .await
-        .context("get OIDC token")?
-        .access_token;
-
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        header::HeaderValue::from_str(&format!("Bearer {auth_token}"))?,
-    );
-
-    let timeout = std::env::var("REQUEST_TIMEOUT")
-        .ok()
-        .map(|value| humantime::Duration::from_str(&value))
-        .transpose()?
-        .map(|v| v.into())
-        .unwrap_or(Duration::from_secs(300));
+    // Fail early if no token can be acquired. Requests get the current token attached when they are sent, as
+    // tokens may expire during the run.
+    provider.provide_token().await.context("get OIDC token")?;
+    utils::GooseUserData::of(user).token_provider = Some(provider.clone());
 
     // Build a custom client.
     let builder = reqwest_12::Client::builder()
-        .default_headers(headers)
         .user_agent("loadtest-ua")
         .timeout(timeout);
 
@@ -0,0 +1,304 @@
+//! Monitoring the analysis graph cache, before and during a run.
+
+use crate::{
+    oidc::OpenIdTokenProvider,
+    server_metrics::{self, Sample, Series},
+};
+use anyhow::{Context, bail};
+use reqwest_12::{Client, RequestBuilder};
+use serde_json::Value;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{sync::Mutex, task::JoinHandle};
+use urlencoding::encode;
+
+/// The state of the analysis graph cache required before starting a run
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum CacheGate {
+    /// Start right away
+    #[default]
+    Any,
+    /// Load the graphs of the scenario components, and wait until no more graphs get loaded
+    Warm,
+    /// Require an empty cache, failing the run otherwise
+    Cold,
+}
+
+/// The status of the analysis graph cache
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) struct Status {
+    /// The number of SBOMs known to the analysis
+    pub sbom_count: Option<u64>,
+    /// The number of graphs loaded into the cache
+    pub graph_count: Option<u64>,
+}
+
+impl From<&Value> for Status {
+    fn from(body: &Value) -> Self {
+        Self {
+            sbom_count: body["sbom_count"].as_u64(),
+            graph_count: body["graph_count"].as_u64(),
+        }
+    }
+}
+
+/// A sample of the analysis status, taken during a run
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct StatusSample {
+    /// Seconds since the monitor started
+    pub elapsed: u64,
+    pub sbom_count: Option<u64>,
+    pub graph_count: Option<u64>,
+    /// Whether graphs got loaded since the previous sample
+    pub loading: bool,
+}
+
+/// Reads the analysis status using the REST API, outside of goose.
+#[derive(Clone)]
+pub(crate) struct AnalysisClient {
+    client: Client,
+    host: String,
+    provider: Option<OpenIdTokenProvider>,
+}
+
+impl AnalysisClient {
+    pub fn new(host: String, provider: Option<OpenIdTokenProvider>) -> Self {
+        Self {
+            client: Client::new(),
+            host,
+            provider,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<Value> {
+        let request = match &self.provider {
+            Some(provider) => provider.authorize(request).await?,
+            None => request,
+        };
+
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn get(&self, path: &str) -> anyhow::Result<Value> {
+        let url = format!("{}{path}", self.host.trim_end_matches('/'));
+        self.send(self.client.get(url))
+            .await
+            .with_context(|| format!("failed to get {path}"))
+    }
+
+    pub async fn status(&self) -> anyhow::Result<Status> {
+        Ok(Status::from(&self.get("/api/v3/analysis/status").await?))
+    }
+
+    /// Search a component, loading the graphs of all SBOMs containing it
+    async fn search(&self, name: &str) -> anyhow::Result<()> {
+        self.get(&format!("/api/v3/analysis/component?q={}", encode(name)))
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Check if graphs got loaded between two samples
+fn loading(previous: Option<u64>, current: Option<u64>) -> bool {
+    matches!((previous, current), (Some(previous), Some(current)) if current > previous)
+}
+
+/// Wait for the analysis graph cache to reach the state required by the gate.
+pub(crate) async fn gate(
+    client: &AnalysisClient,
+    gate: CacheGate,
+    names: &[String],
+    interval: Duration,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    match gate {
+        CacheGate::Any => Ok(()),
+        CacheGate::Cold => match client.status().await?.graph_count {
+            Some(0) => Ok(()),
+            Some(graphs) => bail!(
+                "the analysis graph cache holds {graphs} graphs, restart trustify to start with a cold cache"
+            ),
+            None => bail!("the analysis status doesn't report a graph count"),
+        },
+        CacheGate::Warm => {
+            let start = Instant::now();
+            log::info!(
+                "Warming up the analysis graph cache, using {} components",
+                names.len()
+            );
+
+            for name in names {
+                if let Err(err) = client.search(name).await {
+                    log::warn!("Unable to warm up the graphs of '{name}': {err:#}");
+                }
+            }
+
+            // graphs may still be loading in the background, wait until their number settles
+            let mut previous = client.status().await?.graph_count;
+            loop {
+                if start.elapsed() > timeout {
+                    bail!("the analysis graph cache didn't settle within {timeout:?}");
+                }
+                tokio::time::sleep(interval).await;
+                let current = client.status().await?.graph_count;
+                if !loading(previous, current) {
+                    log::info!(
+                        "Analysis graph cache is warm, holding {} graphs after {:?}",
+                        current.unwrap_or_default(),
+                        start.elapsed()
+                    );
+                    return Ok(());
+                }
+                previous = current;
+            }
+        }
+    }
+}
+
+/// Polls the analysis status in the background, for the duration of a run.
+pub(crate) struct StatusMonitor {
+    samples: Arc<Mutex<Vec<StatusSample>>>,
+    task: JoinHandle<()>,
+}
+
+impl StatusMonitor {
+    pub fn start(client: AnalysisClient, interval: Duration) -> Self {
+        let samples: Arc<Mutex<Vec<StatusSample>>> = Arc::default();
+
+        let task = tokio::spawn({
+            let samples = samples.clone();
+            async move {
+                let start = Instant::now();
+                let mut ticks = tokio::time::interval(interval);
+                loop {
+                    ticks.tick().await;
+                    let status = match client.status().await {
+                        Ok(status) => status,
+                        Err(err) => {
+                            log::warn!("Unable to poll the analysis status: {err:#}");
+                            continue;
+                        }
+                    };
+
+                    let mut samples = samples.lock().await;
+                    let previous = samples.last().and_then(|sample| sample.graph_count);
+                    samples.push(StatusSample {
+                        elapsed: start.elapsed().as_secs(),
+                        sbom_count: status.sbom_count,
+                        graph_count: status.graph_count,
+                        loading: loading(previous, status.graph_count),
+                    });
+                }
+            }
+        });
+
+        Self { samples, task }
+    }
+
+    /// Stop polling, returning the samples taken so far.
+    pub async fn stop(self) -> Vec<StatusSample> {
+        self.task.abort();
+        let _ = self.task.await;
+        std::mem::take(&mut *self.samples.lock().await)
+    }
+}
+
+/// Print a summary of the samples
+pub(crate) fn print_summary(samples: &[StatusSample]) {
+    let graphs: Vec<_> = samples
+        .iter()
+        .filter_map(|sample| sample.graph_count)
+        .collect();
+    let (Some(first), Some(last), Some(max)) = (graphs.first(), graphs.last(), graphs.iter().max())
+    else {
+        println!("No analysis status samples");
+        return;
+    };
+
+    let loading = samples.iter().filter(|sample| sample.loading).count();
+    println!(
+        "Graphs: {first} at the start, {last} at the end, {max} at most. Loading in {loading} of {} samples.",
+        samples.len()
+    );
+}
+
+/// A section of an HTML report, charting the samples.
+pub(crate) fn html(samples: &[StatusSample]) -> String {
+    let series = |name: &str, value: fn(&StatusSample) -> Option<u64>| Series {
+        name: name.into(),
+        samples: samples
+            .iter()
+            .filter_map(|sample| {
+                value(sample).map(|value| Sample {
+                    elapsed: sample.elapsed,
+                    value: value as f64,
+                })
+            })
+            .collect(),
+    };
+
+    server_metrics::chart(
+        "Analysis Status",
+        "Polled from the analysis status of the server during the run. Loading is 1 while graphs got loaded since the previous sample.",
+        &[
+            series("graph_count", |sample| sample.graph_count),
+            series("sbom_count", |sample| sample.sbom_count),
+            series("loading", |sample| Some(sample.loading.into())),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn status() {
+        assert_eq!(
+            Status::from(&json!({ "sbom_count": 12, "graph_count": 3 })),
+            Status {
+                sbom_count: Some(12),
+                graph_count: Some(3),
+            }
+        );
+        assert_eq!(Status::from(&json!({})), Status::default());
+    }
+
+    #[test]
+    fn graphs_loading() {
+        assert!(loading(Some(1), Some(2)));
+        assert!(!loading(Some(2), Some(2)));
+        // graphs may get evicted
+        assert!(!loading(Some(2), Some(1)));
+        assert!(!loading(None, Some(2)));
+    }
+
+    #[test]
+    fn chart() {
+        let samples = [
+            StatusSample {
+                elapsed: 0,
+                sbom_count: Some(10),
+                graph_count: Some(1),
+                loading: false,
+            },
+            StatusSample {
+                elapsed: 10,
+                sbom_count: None,
+                graph_count: Some(3),
+                loading: true,
+            },
+        ];
+        let html = html(&samples);
+
+        assert!(html.contains("<h2>Analysis Status</h2>"));
+        assert!(html.contains("<h3>graph_count</h3>"));
+        assert!(html.contains(r#"<polyline points="100.0,190.0 800.0,10.0""#));
+        // the SBOM count is missing from the second sample, leaving a constant series
+        assert!(html.contains(r#"<polyline points="100.0,100.0""#));
+        assert!(html.contains("<h3>loading</h3>"));
+    }
+}
@@ -2,36 +2,41 @@ use anyhow::Context;
 use core::fmt;
 use openid::TemporalBearerGuard;
 use reqwest::Url;
-use std::fmt::{Debug, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 use std::ops::Deref;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::RwLock;
 
 #[derive(Clone, Debug, PartialEq, Eq, clap::Args)]
 #[command(next_help_heading = "OIDC client configuration")]
+#[group(requires_all = ["oidc_client_id", "oidc_client_secret", "oidc_issuer_url"])]
 pub struct OpenIdTokenProviderConfigArguments {
     #[arg(
         id = "oidc_client_id",
         long = "oidc-client-id",
-        env = "OIDC_PROVIDER_CLIENT_ID"
+        env = "CLIENT_ID",
+        required = false
     )]
     pub client_id: String,
     #[arg(
         id = "oidc_client_secret",
         long = "oidc-client-secret",
-        env = "OIDC_PROVIDER_CLIENT_SECRET"
+        env = "CLIENT_SECRET",
+        required = false
     )]
     pub client_secret: String,
     #[arg(
         id = "oidc_issuer_url",
         long = "oidc-issuer-url",
-        env = "OIDC_PROVIDER_ISSUER_URL"
+        env = "ISSUER_URL",
+        required = false
     )]
     pub issuer_url: String,
     #[arg(
         id = "oidc_refresh_before",
         long = "oidc-refresh-before",
-        env = "OIDC_PROVIDER_REFRESH_BEFORE",
+        env = "OIDC_REFRESH_BEFORE",
         default_value = "30s"
     )]
     pub refresh_before: humantime::Duration,
@@ -39,7 +44,7 @@ pub struct OpenIdTokenProviderConfigArguments {
     #[arg(
         id = "oidc_insecure_tls",
         long = "oidc-insecure-tls",
-        env = "OIDC_PROVIDER_TLS_INSECURE",
+        env = "OIDC_TLS_INSECURE",
         default_value = "false"
     )]
     pub tls_insecure: bool,
@@ -51,6 +56,35 @@ pub struct OpenIdTokenProvider {
     client: Arc<::openid::Client>,
     current_token: Arc<RwLock<Option<openid::TemporalBearerGuard>>>,
     refresh_before: chrono::Duration,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    issued: AtomicU64,
+    refreshed: AtomicU64,
+    failed: AtomicU64,
+}
+
+/// The number of token requests of a provider, shared by all of its clones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TokenMetrics {
+    /// Initial tokens, requested using the client credentials
+    pub issued: u64,
+    /// Tokens refreshed before they expired
+    pub refreshed: u64,
+    /// Failed attempts to get a token
+    pub failed: u64,
+}
+
+impl Display for TokenMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "issued: {}, refreshed: {}, failed: {}",
+            self.issued, self.refreshed, self.failed
+        )
+    }
 }
 
 impl Debug for OpenIdTokenProvider {
@@ -72,6 +106,7 @@ impl OpenIdTokenProvider {
             client: Arc::new(client),
             current_token: Arc::new(RwLock::new(None)),
             refresh_before,
+            counters: Default::default(),
         }
     }
 
@@ -101,6 +136,24 @@ impl OpenIdTokenProvider {
         ))
     }
 
+    /// The token requests made so far.
+    pub fn metrics(&self) -> TokenMetrics {
+        TokenMetrics {
+            issued: self.counters.issued.load(Ordering::Relaxed),
+            refreshed: self.counters.refreshed.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Add the bearer token to a request, fetching a fresh token if required.
+    pub async fn authorize(
+        &self,
+        request: reqwest_12::RequestBuilder,
+    ) -> anyhow::Result<reqwest_12::RequestBuilder> {
+        let token = self.provide_token().await.context("get OIDC token")?;
+        Ok(request.bearer_auth(token.access_token))
+    }
+
     /// return a fresh token, this may be an existing (non-expired) token
     /// a newly refreshed token.
     pub async fn provide_token(&self) -> Result<openid::Bearer, openid::error::Error> {
@@ -137,15 +190,22 @@ impl OpenIdTokenProvider {
             // if we don't have any token, fetch an initial one
             None => {
                 log::debug!("Fetching initial token... ");
-                self.initial_token().await?
+                self.count(self.initial_token().await, &self.counters.issued)?
             }
             // if we have an expired one, refresh it
             Some(current_token) => {
                 log::debug!("Refreshing token ... ");
-                match current_token.as_ref().refresh_token.is_some() {
-                    true => self.client.refresh_token(current_token, None).await?.into(),
-                    false => self.initial_token().await?,
-                }
+                let next_token: Result<TemporalBearerGuard, _> =
+                    match current_token.as_ref().refresh_token.is_some() {
+                        true => self
+                            .client
+                            .refresh_token(current_token, None)
+                            .await
+                            .map(Into::into)
+                            .map_err(Into::into),
+                        false => self.initial_token().await,
+                    };
+                self.count(next_token, &self.counters.refreshed)?
             }
         };
 
@@ -159,6 +219,21 @@ impl OpenIdTokenProvider {
         Ok(result)
     }
 
+    fn count<T>(
+        &self,
+        result: Result<T, openid::error::Error>,
+        success: &AtomicU64,
+    ) -> Result<T, openid::error::Error> {
+        match &result {
+            Ok(_) => success.fetch_add(1, Ordering::Relaxed),
+            Err(err) => {
+                log::warn!("Failed to get OIDC token: {err}");
+                self.counters.failed.fetch_add(1, Ordering::Relaxed)
+            }
+        };
+        result
+    }
+
     async fn initial_token(&self) -> Result<openid::TemporalBearerGuard, openid::error::Error> {
         Ok(self
             .client
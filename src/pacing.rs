@@ -0,0 +1,235 @@
+//! Constant arrival rates, starting transactions independent of response times (an open model).
+//!
+//! Goose users wait between transactions, so a slower server reduces the load it gets, hiding the latency a user
+//! would experience (coordinated omission). A paced user population starts its transactions on a fixed schedule
+//! instead. A transaction starting late, because all users were busy, is measured from its scheduled start.
+
+use crate::{stats::TransactionStats, utils::wrap_transactions, warmup::Warmup};
+use goose::goose::{Scenario, Transaction};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// The delay after the scheduled start which counts as a late start
+const LATE: Duration = Duration::from_millis(1);
+
+/// Hands out the scheduled starts of the transactions of a user population.
+pub(crate) struct Pacer {
+    /// Transactions per second
+    rate: f64,
+    start: OnceLock<Instant>,
+    next: AtomicU64,
+}
+
+impl Pacer {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            start: OnceLock::new(),
+            next: AtomicU64::new(0),
+        }
+    }
+
+    /// Take the next scheduled start, the schedule begins with the first one.
+    fn slot(&self, now: Instant) -> Instant {
+        let start = *self.start.get_or_init(|| now);
+        let slot = self.next.fetch_add(1, Ordering::Relaxed);
+        start + Duration::from_secs_f64(slot as f64 / self.rate)
+    }
+
+    /// The number of scheduled starts so far
+    fn scheduled(&self) -> u64 {
+        self.next.load(Ordering::Relaxed)
+    }
+}
+
+/// The measurements of a paced transaction, in milliseconds.
+#[derive(Default)]
+struct Measurements {
+    /// From the actual start to the end, the time spent by the server
+    service: TransactionStats,
+    /// From the scheduled start to the end, including the time waiting for a free user
+    latency: TransactionStats,
+    /// Runs starting after their scheduled start
+    late: usize,
+}
+
+/// The paced user populations of a run, and the measurements of their transactions.
+pub(crate) struct Pacing {
+    pacers: Vec<(String, f64, Arc<Pacer>)>,
+    measurements: Arc<Mutex<BTreeMap<String, Measurements>>>,
+    /// Transactions of the warm-up are recorded there, instead of in the measurements
+    warmup: Arc<Warmup>,
+}
+
+impl Pacing {
+    pub fn new(warmup: Arc<Warmup>) -> Self {
+        Self {
+            pacers: vec![],
+            measurements: Default::default(),
+            warmup,
+        }
+    }
+
+    /// Pace the transactions of a user population, which share the rate (transactions per second).
+    pub fn pace(&mut self, population: &str, rate: f64, scenario: Scenario) -> Scenario {
+        let pacer = Arc::new(Pacer::new(rate));
+        self.pacers
+            .push((population.to_string(), rate, pacer.clone()));
+
+        wrap_transactions(scenario, |transaction| {
+            self.paced(transaction, pacer.clone())
+        })
+    }
+
+    fn paced(&self, mut transaction: Transaction, pacer: Arc<Pacer>) -> Transaction {
+        let function = transaction.function.clone();
+        let name = transaction.name.clone();
+        let measurements = self.measurements.clone();
+        let warmup = self.warmup.clone();
+
+        transaction.function = Arc::new(move |user| {
+            let function = function.clone();
+            let name = name.clone();
+            let pacer = pacer.clone();
+            let measurements = measurements.clone();
+            let warmup = warmup.clone();
+            Box::pin(async move {
+                let slot = pacer.slot(Instant::now());
+                tokio::time::sleep_until(slot.into()).await;
+
+                let start = Instant::now();
+                let warming_up = warmup.begin(start);
+                let result = function(user).await;
+                let end = Instant::now();
+
+                warmup
+                    .record(&name, warming_up, end - start, result.is_err())
+                    .await;
+                if warming_up {
+                    return result;
+                }
+
+                let mut measurements = measurements.lock().await;
+                let entry = measurements.entry(name.clone()).or_default();
+                let failed = result.is_err();
+                entry.service.name.clone_from(&name);
+                entry.service.record(end - start, failed);
+                entry.latency.name.clone_from(&name);
+                entry
+                    .latency
+                    .record(end.saturating_duration_since(slot), failed);
+                if start.saturating_duration_since(slot) > LATE {
+                    entry.late += 1;
+                }
+
+                result
+            })
+        });
+
+        transaction
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pacers.is_empty()
+    }
+
+    /// Replace the run-times of the paced transactions with their service times, which exclude waiting for the
+    /// scheduled start.
+    pub async fn service_times(&self, stats: &mut [TransactionStats]) {
+        let measurements = self.measurements.lock().await;
+        for stats in stats {
+            if let Some(measurements) = measurements.get(&stats.name) {
+                stats.times.clone_from(&measurements.service.times);
+                stats.total_time = measurements.service.total_time;
+            }
+        }
+    }
+
+    /// The latencies of the paced transactions, measured from their scheduled starts
+    pub async fn latencies(&self) -> Vec<TransactionStats> {
+        self.measurements
+            .lock()
+            .await
+            .values()
+            .map(|measurements| measurements.latency.clone())
+            .collect()
+    }
+
+    /// Print the achieved rates of the populations, and the latencies of their transactions
+    pub async fn print_table(&self, duration: usize) {
+        for (population, rate, pacer) in &self.pacers {
+            let achieved = match duration {
+                0 => 0.0,
+                duration => pacer.scheduled() as f64 / duration as f64,
+            };
+            println!("{population}: {rate:.2}/s targeted, {achieved:.2}/s started");
+        }
+
+        let measurements = self.measurements.lock().await;
+        let width = measurements
+            .keys()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max("TRANSACTION".len());
+
+        println!(
+            "\n{:<width$}  {:>7}  {:>11}  {:>11}  {:>11}  {:>11}  {:>7}",
+            "TRANSACTION",
+            "COUNT",
+            "SERVICE P95",
+            "LATENCY P50",
+            "LATENCY P95",
+            "LATENCY P99",
+            "LATE"
+        );
+        for (name, measurements) in measurements.iter() {
+            println!(
+                "{:<width$}  {:>7}  {:>9}ms  {:>9}ms  {:>9}ms  {:>9}ms  {:>7}",
+                name,
+                measurements.latency.count,
+                measurements.service.percentile(0.95),
+                measurements.latency.percentile(0.5),
+                measurements.latency.percentile(0.95),
+                measurements.latency.percentile(0.99),
+                measurements.late,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn schedule() {
+        let pacer = Pacer::new(4.0);
+        let now = Instant::now();
+
+        assert_eq!(pacer.slot(now), now);
+        assert_eq!(pacer.slot(now), now + Duration::from_millis(250));
+        // independent of when the slot is taken
+        assert_eq!(
+            pacer.slot(now + Duration::from_secs(10)),
+            now + Duration::from_millis(500)
+        );
+        assert_eq!(pacer.scheduled(), 3);
+    }
+
+    #[test]
+    fn fractional_rate() {
+        let pacer = Pacer::new(0.5);
+        let now = Instant::now();
+
+        pacer.slot(now);
+        assert_eq!(pacer.slot(now), now + Duration::from_secs(2));
+    }
+}
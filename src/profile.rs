@@ -0,0 +1,362 @@
+//! Load profiles, stepping the load through stages, and searching the capacity of an instance.
+
+use crate::{
+    baseline::{TransactionSummary, summaries},
+    statements::Statement,
+    stats::TransactionStats,
+};
+use anyhow::{Context, bail};
+use serde::{Deserialize, Deserializer};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::BufReader,
+    time::Duration,
+};
+
+/// Stages of load, run one after the other.
+#[derive(Clone, PartialEq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LoadProfile {
+    /// Stop at the first stage violating the thresholds, reporting the capacity. The load is only increased up to the
+    /// last stage, use a ramp to generate stages up to a maximum.
+    #[serde(default)]
+    pub capacity_search: bool,
+    /// The stages, generated by the ramp if there is one
+    #[serde(default)]
+    pub stages: Vec<Stage>,
+    /// Generate the stages, instead of listing them
+    #[serde(default)]
+    pub ramp: Option<Ramp>,
+}
+
+/// Stages adding the same number of users each, up to a maximum.
+#[derive(Clone, PartialEq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Ramp {
+    /// The users of the first stage
+    pub start_users: usize,
+    /// The users added by every following stage
+    pub step_users: usize,
+    /// The users of the last stage, at most
+    pub max_users: usize,
+    /// How long to run each stage, like `5m`
+    #[serde(deserialize_with = "duration")]
+    pub hold: Duration,
+}
+
+impl Ramp {
+    fn stages(&self) -> anyhow::Result<Vec<Stage>> {
+        if self.start_users == 0 || self.step_users == 0 {
+            bail!("a ramp must start with and add at least one user");
+        }
+        if self.max_users < self.start_users {
+            bail!("the maximum users of a ramp must not be less than its start users");
+        }
+
+        Ok((self.start_users..=self.max_users)
+            .step_by(self.step_users)
+            .map(|users| Stage {
+                users: Some(users),
+                rate_scale: default_rate_scale(),
+                hold: self.hold,
+            })
+            .collect())
+    }
+}
+
+/// A single stage of a load profile.
+#[derive(Clone, PartialEq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Stage {
+    /// The number of users, defaults to the one of the goose options
+    #[serde(default)]
+    pub users: Option<usize>,
+    /// The factor applied to the rates of the paced users of the workload
+    #[serde(default = "default_rate_scale")]
+    pub rate_scale: f64,
+    /// How long to run the stage, like `5m`
+    #[serde(deserialize_with = "duration")]
+    pub hold: Duration,
+}
+
+fn default_rate_scale() -> f64 {
+    1.0
+}
+
+/// Parse a humantime duration, like `90s` or `5m`.
+fn duration<'de, D>(d: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    humantime::parse_duration(&String::deserialize(d)?).map_err(serde::de::Error::custom)
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.users {
+            Some(users) => write!(f, "{users} users")?,
+            None => f.write_str("default users")?,
+        }
+        if self.rate_scale != 1.0 {
+            write!(f, ", rates x{}", self.rate_scale)?;
+        }
+        write!(f, ", for {}", humantime::format_duration(self.hold))
+    }
+}
+
+impl Stage {
+    /// The goose options of the stage, overriding the ones provided on the command line
+    pub fn goose_args(&self) -> Vec<String> {
+        let mut args = vec!["--run-time".to_string(), self.hold.as_secs().to_string()];
+        if let Some(users) = self.users {
+            args.extend(["--users".to_string(), users.to_string()]);
+        }
+        args
+    }
+}
+
+impl LoadProfile {
+    pub fn load(file: &str) -> anyhow::Result<Self> {
+        let mut profile: Self = serde_json5::from_reader(BufReader::new(
+            std::fs::File::open(file).with_context(|| format!("opening load profile: {file}"))?,
+        ))
+        .context("reading load profile")?;
+
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// Validate the profile, generating the stages of its ramp.
+    fn validate(&mut self) -> anyhow::Result<()> {
+        if let Some(ramp) = &self.ramp {
+            if !self.stages.is_empty() {
+                bail!("a load profile has either stages or a ramp, not both");
+            }
+            self.stages = ramp.stages()?;
+        }
+
+        if self.stages.is_empty() {
+            bail!("a load profile requires at least one stage");
+        }
+        for (index, stage) in self.stages.iter().enumerate() {
+            if stage.hold.as_secs() == 0 {
+                bail!("stage {} must be held for at least one second", index + 1);
+            }
+            if !(stage.rate_scale.is_finite() && stage.rate_scale > 0.0) {
+                bail!("the rate scale of stage {} must be positive", index + 1);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of a stage.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct StageResult {
+    /// The users of the stage, `None` for the default of the goose options
+    pub users: Option<usize>,
+    pub rate_scale: f64,
+    /// The duration of the stage, in seconds
+    pub duration: usize,
+    /// All transactions per second
+    pub throughput: f64,
+    /// The ratio of failed transactions, 0.0 to 1.0
+    pub error_rate: f64,
+    /// Whether the stage met the thresholds, `None` without thresholds
+    pub passed: Option<bool>,
+    pub transactions: Vec<TransactionSummary>,
+    /// The top SQL statements of the stage
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statements: Vec<Statement>,
+}
+
+impl StageResult {
+    pub fn new(
+        stage: &Stage,
+        duration: usize,
+        stats: &[TransactionStats],
+        passed: Option<bool>,
+    ) -> Self {
+        let count: usize = stats.iter().map(|stats| stats.count).sum();
+        let failed: usize = stats.iter().map(|stats| stats.failed).sum();
+
+        Self {
+            users: stage.users,
+            rate_scale: stage.rate_scale,
+            duration,
+            throughput: match duration {
+                0 => 0.0,
+                duration => count as f64 / duration as f64,
+            },
+            error_rate: match count {
+                0 => 0.0,
+                count => failed as f64 / count as f64,
+            },
+            passed,
+            transactions: summaries(duration, stats),
+            statements: vec![],
+        }
+    }
+}
+
+/// The stage with the highest throughput, out of the stages before the first one violating the thresholds.
+///
+/// `None` if the first stage already violated them, or there are no thresholds.
+pub(crate) fn capacity(stages: &[StageResult]) -> Option<(usize, &StageResult)> {
+    stages
+        .iter()
+        .enumerate()
+        .take_while(|(_, stage)| stage.passed == Some(true))
+        .max_by(|(_, a), (_, b)| a.throughput.total_cmp(&b.throughput))
+}
+
+/// Print the stages as a table
+pub(crate) fn print_table(stages: &[StageResult]) {
+    println!(
+        "{:>5}  {:>7}  {:>10}  {:>8}  {:>10}  {:>7}  RESULT",
+        "STAGE", "USERS", "RATE SCALE", "DURATION", "THROUGHPUT", "ERRORS"
+    );
+    for (index, stage) in stages.iter().enumerate() {
+        println!(
+            "{:>5}  {:>7}  {:>10}  {:>7}s  {:>8.2}/s  {:>6.2}%  {}",
+            index + 1,
+            stage
+                .users
+                .map(|users| users.to_string())
+                .unwrap_or_else(|| "-".into()),
+            stage.rate_scale,
+            stage.duration,
+            stage.throughput,
+            stage.error_rate * 100.0,
+            match stage.passed {
+                Some(true) => "pass",
+                Some(false) => "FAIL",
+                None => "-",
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stage(throughput: f64, passed: Option<bool>) -> StageResult {
+        StageResult {
+            users: Some(10),
+            rate_scale: 1.0,
+            duration: 60,
+            throughput,
+            error_rate: 0.0,
+            passed,
+            transactions: vec![],
+            statements: vec![],
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let profile: LoadProfile = serde_json5::from_str(
+            r#"{
+  capacity_search: true,
+  stages: [
+    { users: 10, hold: "5m" },
+    { users: 20, rate_scale: 2, hold: "90s" },
+  ],
+}"#,
+        )
+        .expect("must parse");
+
+        assert!(profile.capacity_search);
+        assert_eq!(profile.stages[0].rate_scale, 1.0);
+        assert_eq!(profile.stages[1].hold, Duration::from_secs(90));
+        assert_eq!(
+            profile.stages[1].goose_args(),
+            ["--run-time", "90", "--users", "20"]
+        );
+        assert_eq!(
+            profile.stages[1].to_string(),
+            "20 users, rates x2, for 1m 30s"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let profile = |json: &str| {
+            serde_json5::from_str::<LoadProfile>(json)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut profile| profile.validate())
+        };
+
+        profile(r#"{ stages: [] }"#).expect_err("must have stages");
+        profile(r#"{ stages: [{ hold: "0s" }] }"#).expect_err("must be held");
+        profile(r#"{ stages: [{ hold: "1m", rate_scale: 0 }] }"#).expect_err("must be positive");
+        profile(r#"{ stages: [{ hold: "1m", user: 10 }] }"#).expect_err("must be known fields");
+        profile(r#"{ stages: [{ hold: "1m" }] }"#).expect("must be valid");
+    }
+
+    #[test]
+    fn ramp() {
+        let mut profile: LoadProfile = serde_json5::from_str(
+            r#"{
+  capacity_search: true,
+  ramp: { start_users: 10, step_users: 20, max_users: 60, hold: "5m" },
+}"#,
+        )
+        .expect("must parse");
+        profile.validate().expect("must be valid");
+
+        assert_eq!(
+            profile
+                .stages
+                .iter()
+                .map(|stage| stage.users)
+                .collect::<Vec<_>>(),
+            [Some(10), Some(30), Some(50)]
+        );
+        assert!(
+            profile
+                .stages
+                .iter()
+                .all(|stage| stage.hold == Duration::from_secs(300) && stage.rate_scale == 1.0)
+        );
+
+        let profile = |json: &str| {
+            serde_json5::from_str::<LoadProfile>(json)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut profile| profile.validate())
+        };
+        profile(r#"{ ramp: { start_users: 0, step_users: 10, max_users: 50, hold: "1m" } }"#)
+            .expect_err("must start with users");
+        profile(r#"{ ramp: { start_users: 10, step_users: 0, max_users: 50, hold: "1m" } }"#)
+            .expect_err("must add users");
+        profile(r#"{ ramp: { start_users: 10, step_users: 10, max_users: 5, hold: "1m" } }"#)
+            .expect_err("must not exceed the maximum");
+        profile(r#"{ ramp: { start_users: 10, step_users: 10, max_users: 50, hold: "0s" } }"#)
+            .expect_err("must be held");
+        profile(
+            r#"{ stages: [{ hold: "1m" }], ramp: { start_users: 10, step_users: 10, max_users: 50, hold: "1m" } }"#,
+        )
+        .expect_err("must not have both");
+    }
+
+    #[test]
+    fn capacity_before_violation() {
+        let stages = [
+            stage(10.0, Some(true)),
+            stage(19.0, Some(true)),
+            stage(18.0, Some(true)),
+            stage(25.0, Some(false)),
+            stage(30.0, Some(true)),
+        ];
+        let (index, stage) = capacity(&stages).expect("must have a capacity");
+        assert_eq!(index, 1);
+        assert_eq!(stage.throughput, 19.0);
+    }
+
+    #[test]
+    fn no_capacity() {
+        assert_eq!(capacity(&[stage(10.0, Some(false))]), None);
+        assert_eq!(capacity(&[stage(10.0, None)]), None);
+    }
+}
@@ -1,9 +1,14 @@
+use super::request;
+use crate::assertion::{Assertions, JsonCheck, verify};
+use crate::generator::advisory::AdvisoryGenerator;
+use crate::labels::{Kind, LabelSnapshot};
 use crate::utils::GooseUserData;
 use anyhow::Context;
-use goose::goose::{GooseMethod, GooseRequest, GooseUser, TransactionError, TransactionResult};
+use goose::goose::{GooseMethod, GooseUser, TransactionError, TransactionResult};
 use rand::prelude::*;
-use reqwest_12::{Client, RequestBuilder, get};
+use reqwest_12::get;
 use serde_json::json;
+use std::sync::Arc;
 use urlencoding::encode;
 
 pub async fn get_advisory_total(host: String) -> Result<u64, anyhow::Error> {
@@ -34,7 +39,7 @@ pub async fn find_random_advisory(
     let offset = rand::rng().random_range(0..total_advisories);
     let url = format!("/api/v3/advisory?offset={}&limit=1", offset);
 
-    let response = user.get(&url).await?;
+    let response = request::get(user, &url).await?;
     let json_data = response.response?.json::<serde_json::Value>().await?;
 
     if let Some(items) = json_data.get("items").and_then(|i| i.as_array())
@@ -43,9 +48,7 @@ pub async fn find_random_advisory(
     {
         log::info!("Listing advisory with offset {}: {}", offset, id);
 
-        user.set_session_data(GooseUserData {
-            advisory_id: Some(id.to_string()),
-        });
+        GooseUserData::of(user).advisory_id = Some(id.to_string());
         return Ok(());
     }
 
@@ -55,12 +58,33 @@ pub async fn find_random_advisory(
     ))))
 }
 
+/// Upload a newly generated advisory, the latency of the request being the ingestion time of the document.
+pub async fn upload_advisory(
+    generator: Arc<AdvisoryGenerator>,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    let document = generator.generate(&mut rand::rng());
+    let response = request::post_json(user, "/api/v3/advisory", &document).await?;
+
+    verify(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/id")),
+    )
+    .await
+}
+
 pub async fn get_advisory(id: String, user: &mut GooseUser) -> TransactionResult {
     let uri = format!("/api/v3/advisory/{}", encode(&format!("urn:uuid:{}", id)));
 
-    let _response = user.get(&uri).await?;
+    let response = request::get(user, &uri).await?;
 
-    Ok(())
+    verify(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/uuid")),
+    )
+    .await
 }
 
 pub async fn download_advisory(id: String, user: &mut GooseUser) -> TransactionResult {
@@ -69,7 +93,7 @@ pub async fn download_advisory(id: String, user: &mut GooseUser) -> TransactionR
         encode(&format!("urn:uuid:{}", id))
     );
 
-    let _response = user.get(&uri).await?;
+    let _response = request::get(user, &uri).await?;
 
     Ok(())
 }
@@ -81,18 +105,27 @@ pub async fn list_advisory_labels(user: &mut GooseUser) -> TransactionResult {
         1000
     );
 
-    let _response = user.get(&uri).await?;
+    let response = request::get(user, &uri).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 async fn send_advisory_label_request(
+    labels: &LabelSnapshot,
     advisory_id: String,
     user: &mut GooseUser,
     method: GooseMethod,
     source: &str,
-    client_method: fn(&Client, String) -> RequestBuilder,
 ) -> TransactionResult {
+    labels
+        .record(Kind::Advisory, &advisory_id)
+        .await
+        .map_err(|err| {
+            Box::new(TransactionError::Custom(format!(
+                "unable to record labels: {err:#}"
+            )))
+        })?;
+
     let path = format!("/api/v3/advisory/{}/label", advisory_id);
     let json = json!({
         "source": source,
@@ -101,15 +134,7 @@ async fn send_advisory_label_request(
         "empty": "",
     });
 
-    let url = user.build_url(&path)?;
-
-    let reqwest_request_builder = client_method(&user.client, url);
-    let goose_request = GooseRequest::builder()
-        .method(method)
-        .path(path.as_str())
-        .set_request_builder(reqwest_request_builder.json(&json))
-        .build();
-    let _response = user.request(goose_request).await?;
+    let _response = request::send(user, method, &path, |request| request.json(&json)).await?;
 
     Ok(())
 }
@@ -133,19 +158,25 @@ fn get_advisory_id(user: &mut GooseUser) -> Result<String, Box<TransactionError>
     Ok(advisory_id)
 }
 
-pub async fn put_advisory_labels(user: &mut GooseUser) -> TransactionResult {
+pub async fn put_advisory_labels(
+    labels: Arc<LabelSnapshot>,
+    user: &mut GooseUser,
+) -> TransactionResult {
     let advisory_id = get_advisory_id(user)?;
     send_advisory_label_request(
+        &labels,
         advisory_id,
         user,
         GooseMethod::Put,
         "It's a put request",
-        Client::put,
     )
     .await
 }
 
-pub async fn patch_advisory_labels(user: &mut GooseUser) -> TransactionResult {
+pub async fn patch_advisory_labels(
+    labels: Arc<LabelSnapshot>,
+    user: &mut GooseUser,
+) -> TransactionResult {
     let advisory_id = {
         let goose_user_data = user
             .get_session_data_mut::<GooseUserData>()
@@ -162,11 +193,11 @@ pub async fn patch_advisory_labels(user: &mut GooseUser) -> TransactionResult {
         })?
     };
     send_advisory_label_request(
+        &labels,
         advisory_id,
         user,
         GooseMethod::Patch,
         "It's a patch request",
-        Client::patch,
     )
     .await
 }
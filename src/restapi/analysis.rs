@@ -1,34 +1,298 @@
-#![allow(unused)]
-
+use super::{journey::step, request};
+use crate::{
+    assertion::{Assertions, verify},
+    scenario::{Select, Selector},
+};
 use goose::goose::{GooseUser, TransactionResult};
+use rand::prelude::*;
+use serde_json::Value;
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
 use urlencoding::encode;
 
-pub async fn search_analysis_component(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("/api/v3/analysis/component?q=openssl").await?;
+/// The graph traversals of the component searches, as additional query parameters.
+///
+/// Covers walking the graph in both directions, at different depths, and limited to some relationships.
+pub const COMPONENT_SEARCHES: [&str; 7] = [
+    "",
+    "descendants=1",
+    "descendants=3",
+    "ancestors=1",
+    "ancestors=3",
+    "descendants=3&relationships=contains,dependency",
+    "ancestors=3&relationships=dependency",
+];
 
-    Ok(())
+/// The name of a component search transaction, including its traversal.
+pub fn component_search_name(transaction: &str, params: &str) -> String {
+    match params {
+        "" => transaction.to_string(),
+        params => format!("{transaction}[{params}]"),
+    }
+}
+
+async fn search_component(
+    path: &str,
+    names: Selector,
+    params: &'static str,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    let name = names.select(user);
+    let mut uri = format!("{path}?q={}", encode(&name));
+    if !params.is_empty() {
+        uri = format!("{uri}&{params}");
+    }
+
+    let response = request::get(user, &uri).await?;
+
+    verify(user, response, &Assertions::list()).await
+}
+
+pub async fn search_analysis_component(
+    names: Selector,
+    params: &'static str,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    search_component("/api/v3/analysis/component", names, params, user).await
 }
 
 pub async fn get_analysis_component(key: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/analysis/component/{}", encode(&key)))
-        .await?;
+    let response = request::get(
+        user,
+        &format!("/api/v3/analysis/component/{}", encode(&key)),
+    )
+    .await?;
 
-    Ok(())
+    verify(user, response, &Assertions::list().min_items(1)).await
 }
 
-pub async fn search_latest_component(user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get("/api/v3/analysis/latest/component?q=openssl")
-        .await?;
+pub async fn search_latest_component(
+    names: Selector,
+    params: &'static str,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    search_component("/api/v3/analysis/latest/component", names, params, user).await
+}
+
+pub async fn render_sbom_graph_dot(id: String, user: &mut GooseUser) -> TransactionResult {
+    let _response = request::get(
+        user,
+        &format!("/api/v3/analysis/sbom/{}/render.dot", encode(&id)),
+    )
+    .await?;
 
     Ok(())
 }
 
-pub async fn render_sbom_graph_dot(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/analysis/sbom/{}/render.dot", encode(&id)))
+/// The direction of a walk through the analysis graph
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum WalkDirection {
+    /// Towards the dependencies of a component
+    Descendants,
+    /// Towards the components depending on it, up to the products
+    Ancestors,
+    /// Either way, picked for every hop
+    #[default]
+    Both,
+}
+
+impl WalkDirection {
+    /// The query parameters reading the neighbours of a component
+    fn params(&self) -> &'static str {
+        match self {
+            Self::Descendants => "descendants=1",
+            Self::Ancestors => "ancestors=1",
+            Self::Both => "ancestors=1&descendants=1",
+        }
+    }
+
+    /// The fields of a node holding its neighbours
+    fn fields(&self) -> &'static [&'static str] {
+        match self {
+            Self::Descendants => &["descendants"],
+            Self::Ancestors => &["ancestors"],
+            Self::Both => &["ancestors", "descendants"],
+        }
+    }
+}
+
+impl Display for WalkDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Descendants => "descendants",
+            Self::Ancestors => "ancestors",
+            Self::Both => "both",
+        })
+    }
+}
+
+/// The walks of the `GraphWalkUser`.
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct GraphWalkConfig {
+    /// The maximum number of hops after the initial search, a walk ends early at a component without neighbours
+    pub hops: usize,
+    /// The direction to walk into
+    pub direction: WalkDirection,
+}
+
+impl Default for GraphWalkConfig {
+    fn default() -> Self {
+        Self {
+            hops: 5,
+            direction: WalkDirection::default(),
+        }
+    }
+}
+
+/// The neighbours of all nodes of an analysis response, in the direction of the walk.
+fn neighbours(body: &Value, direction: WalkDirection) -> Vec<&Value> {
+    body["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|node| {
+            direction
+                .fields()
+                .iter()
+                .filter_map(|field| node[field].as_array())
+                .flatten()
+        })
+        .collect()
+}
+
+/// The key looking up a node: its first purl, its CPE, or its name.
+fn node_key(node: &Value) -> Option<String> {
+    let first = |field: &str| match &node[field] {
+        Value::Array(values) => values.first().and_then(Value::as_str),
+        value => value.as_str(),
+    };
+
+    first("purl")
+        .or_else(|| first("cpe"))
+        .or_else(|| node["name"].as_str())
+        .filter(|key| !key.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Search a component, then follow one of its ancestors or descendants for up to the configured number of hops.
+///
+/// Every lookup is a request of its own, the first one reading the graph of the SBOM (cold) if it isn't cached yet,
+/// the following ones mostly reading the cached graph (warm).
+pub async fn walk_analysis_graph(
+    names: Selector,
+    config: Arc<GraphWalkConfig>,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    const WALK: &str = "walk_analysis_graph";
+    let params = config.direction.params();
+
+    let name = names.select(user);
+    let mut body = step(
+        user,
+        WALK,
+        &format!("/api/v3/analysis/component?q={}&{params}", encode(&name)),
+        "/api/v3/analysis/component",
+        &Assertions::list(),
+    )
+    .await?;
+
+    for _ in 0..config.hops {
+        let next = neighbours(&body, config.direction)
+            .choose(&mut rand::rng())
+            .and_then(|node| node_key(node));
+        let Some(key) = next else {
+            // a leaf, or a root
+            break;
+        };
+
+        body = step(
+            user,
+            WALK,
+            &format!("/api/v3/analysis/component/{}?{params}", encode(&key)),
+            "/api/v3/analysis/component/{key}",
+            &Assertions::list(),
+        )
         .await?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn search_names() {
+        assert_eq!(
+            component_search_name("search_analysis_component", ""),
+            "search_analysis_component"
+        );
+        assert_eq!(
+            component_search_name("search_latest_component", "ancestors=3"),
+            "search_latest_component[ancestors=3]"
+        );
+    }
+
+    #[test]
+    fn walk_neighbours() {
+        let body = json!({
+            "items": [
+                {
+                    "name": "openssl",
+                    "ancestors": [{ "name": "rhel" }],
+                    "descendants": [{ "name": "zlib" }, { "name": "glibc" }],
+                },
+                { "name": "openssl", "descendants": [] },
+            ],
+            "total": 2,
+        });
+        let names = |direction| -> Vec<_> {
+            neighbours(&body, direction)
+                .iter()
+                .filter_map(|node| node["name"].as_str())
+                .collect()
+        };
+
+        assert_eq!(names(WalkDirection::Descendants), ["zlib", "glibc"]);
+        assert_eq!(names(WalkDirection::Ancestors), ["rhel"]);
+        assert_eq!(names(WalkDirection::Both), ["rhel", "zlib", "glibc"]);
+        assert!(neighbours(&json!({ "items": [] }), WalkDirection::Both).is_empty());
+    }
+
+    #[test]
+    fn walk_node_key() {
+        assert_eq!(
+            node_key(&json!({ "purl": ["pkg:rpm/redhat/zlib@1.2"], "cpe": [], "name": "zlib" }))
+                .as_deref(),
+            Some("pkg:rpm/redhat/zlib@1.2")
+        );
+        assert_eq!(
+            node_key(&json!({ "purl": [], "cpe": ["cpe:/a:redhat:rhel:9"], "name": "rhel" }))
+                .as_deref(),
+            Some("cpe:/a:redhat:rhel:9")
+        );
+        assert_eq!(
+            node_key(&json!({ "purl": [], "name": "zlib" })).as_deref(),
+            Some("zlib")
+        );
+        assert_eq!(node_key(&json!({ "name": "" })), None);
+    }
+
+    #[test]
+    fn walk_config() {
+        let config: GraphWalkConfig =
+            serde_json5::from_str(r#"{ hops: 10, direction: "ancestors" }"#).expect("must parse");
+        assert_eq!(config.hops, 10);
+        assert_eq!(config.direction, WalkDirection::Ancestors);
+        assert_eq!(
+            serde_json5::from_str::<GraphWalkConfig>("{}").expect("must parse"),
+            GraphWalkConfig::default()
+        );
+    }
+}
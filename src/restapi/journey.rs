@@ -0,0 +1,297 @@
+//! User journeys: searching entities, opening one of the results, and following up with its details.
+//!
+//! Every step is a request named after the journey and the endpoint, reporting the latency of that step. The
+//! transaction reports the latency of the whole journey.
+
+use super::request;
+use crate::assertion::{Assertions, JsonCheck, verify_body};
+use goose::goose::{GooseMethod, GooseUser, TransactionError, TransactionResult};
+use rand::prelude::*;
+use serde_json::Value;
+use std::sync::Arc;
+use urlencoding::encode;
+
+/// The searches starting the journeys of the `JourneyUser`.
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct JourneyConfig {
+    /// The SBOM search, as a path with an optional (pre-encoded) query string
+    pub sbom: String,
+    /// The advisory search
+    pub advisory: String,
+    /// The vulnerability search
+    pub vulnerability: String,
+}
+
+impl Default for JourneyConfig {
+    fn default() -> Self {
+        Self {
+            sbom: "/api/v3/sbom?sort=ingested:desc&limit=25".into(),
+            advisory: "/api/v3/advisory?sort=modified:desc&limit=25".into(),
+            vulnerability: "/api/v3/vulnerability?sort=base_score:desc&limit=25".into(),
+        }
+    }
+}
+
+/// Issue a single step of a journey, returning the parsed response.
+///
+/// A failed step fails the transaction, ending the journey.
+pub(super) async fn step(
+    user: &mut GooseUser,
+    journey: &str,
+    path: &str,
+    endpoint: &str,
+    assertions: &Assertions,
+) -> Result<Value, Box<TransactionError>> {
+    let name = format!("{journey}: {endpoint}");
+    let response =
+        request::send_named(user, GooseMethod::Get, path, &name, |request| request).await?;
+
+    let body = verify_body(user, response, assertions).await?;
+
+    serde_json::from_str(&body).map_err(|err| {
+        Box::new(TransactionError::Custom(format!(
+            "invalid response of {path}: {err}"
+        )))
+    })
+}
+
+/// Pick a random entry of the array at `pointer`, returning its `field`.
+fn pick(body: &Value, pointer: &str, field: &str, rng: &mut impl Rng) -> Option<String> {
+    let candidates: Vec<_> = body
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item[field].as_str())
+        .collect();
+
+    candidates.choose(rng).map(|value| value.to_string())
+}
+
+/// Pick a search result, failing the journey if the search found nothing to follow up with.
+fn pick_result(body: &Value, field: &str, search: &str) -> Result<String, Box<TransactionError>> {
+    pick(body, "/items", field, &mut rand::rng()).ok_or_else(|| {
+        Box::new(TransactionError::Custom(format!(
+            "no results to pick from: {search}"
+        )))
+    })
+}
+
+/// The path of an advisory, by the `urn:uuid:` prefixed UUID the API lists it with.
+fn advisory_path(uuid: &str) -> String {
+    format!("/api/v3/advisory/{}", encode(uuid))
+}
+
+/// Search SBOMs, open one, then view its packages, advisories, and related SBOMs.
+pub async fn sbom_journey(config: Arc<JourneyConfig>, user: &mut GooseUser) -> TransactionResult {
+    const JOURNEY: &str = "sbom_journey";
+
+    let results = step(
+        user,
+        JOURNEY,
+        &config.sbom,
+        "/api/v3/sbom",
+        &Assertions::list(),
+    )
+    .await?;
+    let id = encode(&pick_result(&results, "id", &config.sbom)?).into_owned();
+
+    let details = Assertions::json().check(JsonCheck::exists("/id"));
+    let steps = [
+        (format!("/api/v3/sbom/{id}"), "/api/v3/sbom/{id}", details),
+        (
+            format!("/api/v3/sbom/{id}/packages?limit=25"),
+            "/api/v3/sbom/{id}/packages",
+            Assertions::list(),
+        ),
+        (
+            format!("/api/v3/sbom/{id}/advisory"),
+            "/api/v3/sbom/{id}/advisory",
+            Assertions::json(),
+        ),
+        (
+            format!("/api/v3/sbom/{id}/related?limit=25"),
+            "/api/v3/sbom/{id}/related",
+            Assertions::list(),
+        ),
+    ];
+
+    for (path, endpoint, assertions) in steps {
+        step(user, JOURNEY, &path, endpoint, &assertions).await?;
+    }
+
+    Ok(())
+}
+
+/// Search advisories, open one, then view one of its vulnerabilities.
+pub async fn advisory_journey(
+    config: Arc<JourneyConfig>,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    const JOURNEY: &str = "advisory_journey";
+
+    let results = step(
+        user,
+        JOURNEY,
+        &config.advisory,
+        "/api/v3/advisory",
+        &Assertions::list(),
+    )
+    .await?;
+    let uuid = pick_result(&results, "uuid", &config.advisory)?;
+
+    let advisory = step(
+        user,
+        JOURNEY,
+        &advisory_path(&uuid),
+        "/api/v3/advisory/{id}",
+        &Assertions::json().check(JsonCheck::exists("/uuid")),
+    )
+    .await?;
+
+    // not every advisory has vulnerabilities, which ends the journey early
+    if let Some(identifier) = pick(
+        &advisory,
+        "/vulnerabilities",
+        "identifier",
+        &mut rand::rng(),
+    ) {
+        step(
+            user,
+            JOURNEY,
+            &format!("/api/v3/vulnerability/{}", encode(&identifier)),
+            "/api/v3/vulnerability/{id}",
+            &Assertions::json().check(JsonCheck::exists("/identifier")),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Search vulnerabilities, open one, then view one of the advisories describing it.
+pub async fn vulnerability_journey(
+    config: Arc<JourneyConfig>,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    const JOURNEY: &str = "vulnerability_journey";
+
+    let results = step(
+        user,
+        JOURNEY,
+        &config.vulnerability,
+        "/api/v3/vulnerability",
+        &Assertions::list(),
+    )
+    .await?;
+    let identifier = pick_result(&results, "identifier", &config.vulnerability)?;
+
+    let vulnerability = step(
+        user,
+        JOURNEY,
+        &format!("/api/v3/vulnerability/{}", encode(&identifier)),
+        "/api/v3/vulnerability/{id}",
+        &Assertions::json().check(JsonCheck::exists("/identifier")),
+    )
+    .await?;
+
+    if let Some(uuid) = pick(&vulnerability, "/advisories", "uuid", &mut rand::rng()) {
+        step(
+            user,
+            JOURNEY,
+            &advisory_path(&uuid),
+            "/api/v3/advisory/{id}",
+            &Assertions::json().check(JsonCheck::exists("/uuid")),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use serde_json::json;
+
+    #[test]
+    fn pick_item() {
+        let body = json!({
+            "items": [{ "id": "a" }, { "id": "b" }, { "name": "c" }, { "id": 1 }],
+            "total": 4,
+        });
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..10 {
+            let id = pick(&body, "/items", "id", &mut rng).expect("must pick an item");
+            assert!(["a", "b"].contains(&id.as_str()), "unexpected: {id}");
+        }
+    }
+
+    #[test]
+    fn pick_nothing() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            pick(&json!({ "items": [] }), "/items", "id", &mut rng),
+            None
+        );
+        assert_eq!(pick(&json!({ "total": 0 }), "/items", "id", &mut rng), None);
+        assert_eq!(
+            pick(
+                &json!({ "items": [{ "name": "a" }] }),
+                "/items",
+                "id",
+                &mut rng
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn pick_nested() {
+        let advisory = json!({
+            "uuid": "urn:uuid:0195a2c4-7b4e-7a31-9d2e-3f1c6b8e5a07",
+            "vulnerabilities": [{ "identifier": "CVE-2024-1234", "severity": "high" }],
+        });
+        assert_eq!(
+            pick(
+                &advisory,
+                "/vulnerabilities",
+                "identifier",
+                &mut StdRng::seed_from_u64(0)
+            )
+            .as_deref(),
+            Some("CVE-2024-1234")
+        );
+    }
+
+    #[test]
+    fn advisory_uuid() {
+        let vulnerability = json!({
+            "identifier": "CVE-2024-1234",
+            "advisories": [{ "uuid": "urn:uuid:0195a2c4-7b4e-7a31-9d2e-3f1c6b8e5a07" }],
+        });
+        let uuid = pick(
+            &vulnerability,
+            "/advisories",
+            "uuid",
+            &mut StdRng::seed_from_u64(0),
+        )
+        .expect("must pick an advisory");
+
+        assert_eq!(
+            advisory_path(&uuid),
+            "/api/v3/advisory/urn%3Auuid%3A0195a2c4-7b4e-7a31-9d2e-3f1c6b8e5a07"
+        );
+    }
+
+    #[test]
+    fn workload_config() {
+        let config: JourneyConfig =
+            serde_json5::from_str(r#"{ sbom: "/api/v3/sbom?q=name~redhat" }"#).expect("must parse");
+        assert_eq!(config.sbom, "/api/v3/sbom?q=name~redhat");
+        assert_eq!(config.advisory, JourneyConfig::default().advisory);
+    }
+}
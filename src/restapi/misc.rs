@@ -1,84 +1,84 @@
+use super::request;
+use crate::assertion::{Assertions, verify};
 use goose::goose::{GooseUser, TransactionResult};
 use serde_json::json;
 
 pub async fn get_product(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/product/{id}")).await?;
+    let response = request::get(user, &format!("/api/v3/product/{id}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn get_organization(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/organization/{id}")).await?;
+    let response = request::get(user, &format!("/api/v3/organization/{id}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn get_importer(name: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/importer/{name}")).await?;
+    let response = request::get(user, &format!("/api/v3/importer/{name}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn get_importer_report(name: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/importer/{name}/report")).await?;
+    let response = request::get(user, &format!("/api/v3/importer/{name}/report")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::list()).await
 }
 
 pub async fn get_spdx_license(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/license/spdx/license/{id}"))
-        .await?;
+    let response = request::get(user, &format!("/api/v3/license/spdx/license/{id}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn get_weakness(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/weakness/{id}")).await?;
+    let response = request::get(user, &format!("/api/v3/weakness/{id}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn post_extract_sbom_purls(user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .post_json(
-            "/api/v3/ui/extract-sbom-purls",
-            &json!({
-                "spdxVersion": "SPDX-2.3",
-                "dataLicense": "CC0-1.0",
-                "SPDXID": "SPDXRef-DOCUMENT",
-                "name": "load-test-doc",
-                "documentNamespace": "https://example.com/load-test",
-                "creationInfo": {
-                    "created": "2024-01-01T00:00:00Z",
-                    "creators": ["Tool: scale-testing"]
-                },
-                "packages": [
-                    {
-                        "SPDXID": "SPDXRef-Package",
-                        "name": "openssl",
-                        "versionInfo": "3.0.0",
-                        "downloadLocation": "NOASSERTION",
-                        "filesAnalyzed": false,
-                        "externalRefs": [
-                            {
-                                "referenceCategory": "PACKAGE-MANAGER",
-                                "referenceType": "purl",
-                                "referenceLocator": "pkg:rpm/redhat/openssl@3.0.0"
-                            }
-                        ]
-                    }
-                ],
-                "relationships": [
-                    {
-                        "spdxElementId": "SPDXRef-DOCUMENT",
-                        "relationshipType": "DESCRIBES",
-                        "relatedSpdxElement": "SPDXRef-Package"
-                    }
-                ]
-            }),
-        )
-        .await?;
+    let response = request::post_json(
+        user,
+        "/api/v3/ui/extract-sbom-purls",
+        &json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "load-test-doc",
+            "documentNamespace": "https://example.com/load-test",
+            "creationInfo": {
+                "created": "2024-01-01T00:00:00Z",
+                "creators": ["Tool: scale-testing"]
+            },
+            "packages": [
+                {
+                    "SPDXID": "SPDXRef-Package",
+                    "name": "openssl",
+                    "versionInfo": "3.0.0",
+                    "downloadLocation": "NOASSERTION",
+                    "filesAnalyzed": false,
+                    "externalRefs": [
+                        {
+                            "referenceCategory": "PACKAGE-MANAGER",
+                            "referenceType": "purl",
+                            "referenceLocator": "pkg:rpm/redhat/openssl@3.0.0"
+                        }
+                    ]
+                }
+            ],
+            "relationships": [
+                {
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": "SPDXRef-Package"
+                }
+            ]
+        }),
+    )
+    .await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
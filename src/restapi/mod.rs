@@ -1,7 +1,9 @@
 pub mod advisory;
 pub mod analysis;
+pub mod journey;
 pub mod misc;
 pub mod purl;
+pub mod request;
 pub mod sbom;
 pub mod sbom_group;
 pub mod vulnerability;
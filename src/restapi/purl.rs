@@ -1,20 +1,25 @@
+use super::request;
+use crate::assertion::{Assertions, JsonCheck, verify};
 use crate::utils::DisplayVec;
 use goose::goose::{GooseUser, TransactionResult};
 use serde_json::json;
 use urlencoding::encode;
 
 pub async fn get_purl_details(purl_id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/purl/{purl_id}")).await?;
+    let response = request::get(user, &format!("/api/v3/purl/{purl_id}")).await?;
 
-    Ok(())
+    verify(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/uuid")),
+    )
+    .await
 }
 
 pub async fn get_base_purl(key: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/purl/base/{}", encode(&key)))
-        .await?;
+    let response = request::get(user, &format!("/api/v3/purl/base/{}", encode(&key))).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 /// Send a recommend request with a subset of PURLs determined by `batch_size`.
@@ -24,13 +29,13 @@ pub async fn get_recommendations(
     user: &mut GooseUser,
 ) -> TransactionResult {
     let batch: Vec<&String> = purls.0.iter().take(batch_size).collect();
-    let _response = user
-        .post_json(
-            "/api/v3/purl/recommend",
-            &json!({
-             "purls": batch
-            }),
-        )
-        .await?;
-    Ok(())
+    let response = request::post_json(
+        user,
+        "/api/v3/purl/recommend",
+        &json!({
+         "purls": batch
+        }),
+    )
+    .await?;
+    verify(user, response, &Assertions::json()).await
 }
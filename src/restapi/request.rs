@@ -0,0 +1,112 @@
+//! Requests of the REST API, authorized with the current bearer token of the user.
+//!
+//! The token is taken from the [`GooseUserData`] of the user for every request, so that tokens get refreshed
+//! when they are about to expire, instead of failing all requests of long-running users.
+
+use crate::{trace::header_value, utils::GooseUserData};
+use goose::goose::{GooseMethod, GooseRequest, GooseResponse, GooseUser, TransactionError};
+use reqwest_12::RequestBuilder;
+use serde::Serialize;
+use std::time::Instant;
+
+type RequestResult = Result<GooseResponse, Box<TransactionError>>;
+
+/// Send a `GET` request
+pub async fn get(user: &mut GooseUser, path: &str) -> RequestResult {
+    send(user, GooseMethod::Get, path, |request| request).await
+}
+
+/// Send a `POST` request with a JSON body
+pub async fn post_json<T: Serialize + ?Sized>(
+    user: &mut GooseUser,
+    path: &str,
+    json: &T,
+) -> RequestResult {
+    send(user, GooseMethod::Post, path, |request| request.json(json)).await
+}
+
+/// Send a `DELETE` request, reported to goose using the provided name
+pub async fn delete(user: &mut GooseUser, path: &str, name: &str) -> RequestResult {
+    send_named(user, GooseMethod::Delete, path, name, |request| request).await
+}
+
+/// Send a request, customizing the request builder (e.g. to add a body) before it is authorized.
+pub async fn send(
+    user: &mut GooseUser,
+    method: GooseMethod,
+    path: &str,
+    customize: impl FnOnce(RequestBuilder) -> RequestBuilder,
+) -> RequestResult {
+    send_named(user, method, path, path, customize).await
+}
+
+/// Send a request, reported to goose using the provided name instead of its path.
+///
+/// Used for paths containing an ID, which would otherwise show up as a new request in the metrics for every ID.
+///
+/// Requests of traced transactions carry the trace context, see [`crate::trace`].
+pub async fn send_named(
+    user: &mut GooseUser,
+    method: GooseMethod,
+    path: &str,
+    name: &str,
+    customize: impl FnOnce(RequestBuilder) -> RequestBuilder,
+) -> RequestResult {
+    let mut request = customize(user.get_request_builder(&method, path)?);
+
+    let trace = user
+        .get_session_data::<GooseUserData>()
+        .and_then(|data| data.trace.clone());
+    if let Some(trace) = &trace {
+        request = request
+            .header("traceparent", trace.traceparent())
+            .header("X-Loadtest-Scenario", header_value(&trace.scenario))
+            .header("X-Loadtest-Transaction", header_value(&trace.transaction));
+    }
+
+    let provider = user
+        .get_session_data::<GooseUserData>()
+        .and_then(|data| data.token_provider.clone());
+    if let Some(provider) = provider {
+        request = provider
+            .authorize(request)
+            .await
+            .map_err(|err| Box::new(TransactionError::Custom(format!("{err:#}"))))?;
+    }
+
+    let start = Instant::now();
+    let result = user
+        .request(
+            GooseRequest::builder()
+                .method(method)
+                .path(path)
+                .name(name)
+                .set_request_builder(request)
+                .build(),
+        )
+        .await;
+
+    if let Some(trace) = &trace {
+        let elapsed = start.elapsed();
+        if trace.slow.is_some_and(|slow| elapsed >= slow) {
+            log::warn!(
+                "Slow request {name} of {}: {elapsed:?}, trace ID {}",
+                trace.transaction,
+                trace.trace_id
+            );
+        }
+    }
+
+    let live_metrics = user
+        .get_session_data::<GooseUserData>()
+        .and_then(|data| data.live_metrics.clone());
+    if let Some(live_metrics) = live_metrics {
+        let status = match &result {
+            Ok(response) => response.request.status_code,
+            Err(_) => 0,
+        };
+        live_metrics.record_status(name, status).await;
+    }
+
+    result
+}
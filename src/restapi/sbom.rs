@@ -1,100 +1,104 @@
-use goose::goose::{GooseMethod, GooseRequest, GooseUser, TransactionResult};
-use reqwest_12::Client;
-use serde_json::json;
-use std::sync::{
-    Arc,
-    atomic::{AtomicUsize, Ordering},
+use super::request;
+use crate::{
+    assertion::{Assertions, JsonCheck, verify, verify_body},
+    generator::sbom::SbomConfig,
+    scenario::{Select, Selector},
+};
+use anyhow::{Context, bail};
+use goose::goose::{GooseMethod, GooseUser, TransactionError, TransactionResult};
+use serde_json::{Value, json};
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 use urlencoding::encode;
 
 pub async fn get_sbom(sbom_id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/sbom/{sbom_id}")).await?;
+    let response = request::get(user, &format!("/api/v3/sbom/{sbom_id}")).await?;
 
-    Ok(())
+    verify(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/id")),
+    )
+    .await
 }
 
 pub async fn get_sbom_advisories(sbom_id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/sbom/{sbom_id}/advisory"))
-        .await?;
+    let response = request::get(user, &format!("/api/v3/sbom/{sbom_id}/advisory")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn get_sbom_packages(sbom_id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/sbom/{sbom_id}/packages"))
-        .await?;
+    let response = request::get(user, &format!("/api/v3/sbom/{sbom_id}/packages")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::list()).await
 }
 
 pub async fn get_sbom_related(sbom_id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/sbom/{sbom_id}/related")).await?;
+    let response = request::get(user, &format!("/api/v3/sbom/{sbom_id}/related")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::list()).await
 }
 
 pub async fn sbom_by_package(purl: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/sbom/by-package?purl={}", encode(&purl)))
-        .await?;
+    let response = request::get(
+        user,
+        &format!("/api/v3/sbom/by-package?purl={}", encode(&purl)),
+    )
+    .await?;
 
-    Ok(())
+    verify(user, response, &Assertions::list().min_items(1)).await
 }
 
 pub async fn get_sbom_license_ids(sbom_id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!(
-            "/api/v3/sbom/{}/all-license-ids",
-            encode(&sbom_id)
-        ))
-        .await?;
+    let response = request::get(
+        user,
+        &format!("/api/v3/sbom/{}/all-license-ids", encode(&sbom_id)),
+    )
+    .await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn download_sbom(key: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/sbom/{}/download", encode(&key)))
-        .await?;
+    let _response = request::get(user, &format!("/api/v3/sbom/{}/download", encode(&key))).await?;
 
     Ok(())
 }
 
 pub async fn get_sbom_license_export(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/sbom/{}/license-export", encode(&id)))
-        .await?;
+    let _response = request::get(
+        user,
+        &format!("/api/v3/sbom/{}/license-export", encode(&id)),
+    )
+    .await?;
 
     Ok(())
 }
 
 pub async fn count_sbom_by_package(purl: String, user: &mut GooseUser) -> TransactionResult {
-    let url = user.build_url("/api/v3/sbom/count-by-package")?;
-    let goose_request = GooseRequest::builder()
-        .method(GooseMethod::Get)
-        .path("/api/v3/sbom/count-by-package")
-        .set_request_builder(Client::get(&user.client, url).json(&json!([{"purl": purl}])))
-        .build();
-    let _response = user.request(goose_request).await?;
+    let response = request::send(
+        user,
+        GooseMethod::Get,
+        "/api/v3/sbom/count-by-package",
+        |request| request.json(&json!([{"purl": purl}])),
+    )
+    .await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn put_sbom_labels(id: String, user: &mut GooseUser) -> TransactionResult {
-    send_sbom_label_request(id, user, GooseMethod::Put, "load-test", Client::put).await
+    send_sbom_label_request(id, user, GooseMethod::Put, "load-test").await
 }
 
 pub async fn patch_sbom_labels(id: String, user: &mut GooseUser) -> TransactionResult {
-    send_sbom_label_request(
-        id,
-        user,
-        GooseMethod::Patch,
-        "load-test-patch",
-        Client::patch,
-    )
-    .await
+    send_sbom_label_request(id, user, GooseMethod::Patch, "load-test-patch").await
 }
 
 async fn send_sbom_label_request(
@@ -102,7 +106,6 @@ async fn send_sbom_label_request(
     user: &mut GooseUser,
     method: GooseMethod,
     source: &str,
-    client_method: fn(&Client, String) -> reqwest_12::RequestBuilder,
 ) -> TransactionResult {
     use serde_json::json;
     let path = format!("/api/v3/sbom/{}/label", encode(&sbom_id));
@@ -111,27 +114,176 @@ async fn send_sbom_label_request(
         "load-test": "true",
     });
 
-    let url = user.build_url(&path)?;
-    let reqwest_request_builder = client_method(&user.client, url);
-    let goose_request = GooseRequest::builder()
-        .method(method)
-        .path(path.as_str())
-        .set_request_builder(reqwest_request_builder.json(&json))
-        .build();
-    let _response = user.request(goose_request).await?;
+    let _response = request::send(user, method, &path, |request| request.json(&json)).await?;
 
     Ok(())
 }
 
+/// Upload a newly generated SBOM, the latency of the request being the ingestion time of the document.
+pub async fn upload_sbom(config: Arc<SbomConfig>, user: &mut GooseUser) -> TransactionResult {
+    let document = config.generate(&mut rand::rng());
+    let response = request::post_json(user, "/api/v3/sbom", &document).await?;
+
+    verify(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/id")),
+    )
+    .await
+}
+
+/// The source of the disposable SBOMs, which get ingested and deleted again.
+pub enum DisposableSbom {
+    /// Newly generated SBOMs
+    Generated(SbomConfig),
+    /// Copies of existing SBOMs, picked from a pool
+    Copy(Selector),
+}
+
+impl Display for DisposableSbom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Generated(config) => write!(f, "{}:{}", config.format, config.packages),
+            Self::Copy(selector) => write!(f, "copy:{selector}"),
+        }
+    }
+}
+
+/// Ingest a disposable SBOM and delete it again, leaving the dataset unchanged.
+///
+/// Each step is a request of its own, `DELETE /api/v3/sbom/{id}` being the one to look at for the delete latency.
+pub async fn ingest_and_delete_sbom(
+    source: Arc<DisposableSbom>,
+    user: &mut GooseUser,
+) -> TransactionResult {
+    let document = match &*source {
+        DisposableSbom::Generated(config) => config.generate(&mut rand::rng()),
+        DisposableSbom::Copy(selector) => {
+            let sbom_id = selector.select(user);
+            let response = request::send_named(
+                user,
+                GooseMethod::Get,
+                &format!("/api/v3/sbom/{}/download", encode(&sbom_id)),
+                "/api/v3/sbom/{id}/download",
+                |request| request,
+            )
+            .await?;
+            let body = verify_body(user, response, &Assertions::default()).await?;
+            unique_copy(&body).map_err(|err| {
+                Box::new(TransactionError::Custom(format!(
+                    "unable to copy SBOM {sbom_id}: {err:#}"
+                )))
+            })?
+        }
+    };
+
+    let response = request::post_json(user, "/api/v3/sbom", &document).await?;
+    let body = verify_body(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/id")),
+    )
+    .await?;
+    let Some(id) = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|body| body["id"].as_str().map(ToString::to_string))
+    else {
+        return Err(Box::new(TransactionError::Custom(format!(
+            "missing ID of ingested SBOM: {body}"
+        ))));
+    };
+
+    let response = request::delete(
+        user,
+        &format!("/api/v3/sbom/{}", encode(&id)),
+        "/api/v3/sbom/{id}",
+    )
+    .await?;
+
+    verify(user, response, &Assertions::default()).await
+}
+
+/// Make a copy of an SBOM unique, so that it gets ingested as a new document instead of resolving to the original
+/// one, which would then be deleted.
+fn unique_copy(document: &str) -> anyhow::Result<Value> {
+    let mut document: Value = serde_json::from_str(document).context("not a JSON document")?;
+    let id = uuid::Uuid::new_v4();
+
+    let Some(fields) = document.as_object_mut() else {
+        bail!("not a JSON object");
+    };
+    if fields.contains_key("spdxVersion") {
+        fields.insert(
+            "documentNamespace".into(),
+            format!("https://trustify.io/loadtest/{id}").into(),
+        );
+    } else if fields.contains_key("bomFormat") {
+        fields.insert("serialNumber".into(), format!("urn:uuid:{id}").into());
+    } else {
+        bail!("neither an SPDX nor a CycloneDX document");
+    }
+
+    Ok(document)
+}
+
+/// Delete the SBOMs of the pool one after the other, permanently removing them from the dataset.
+///
+/// Fails once all SBOMs of the pool have been deleted.
 pub async fn delete_sbom_from_pool_sequential(
     pool: Vec<String>,
     counter: Arc<AtomicUsize>,
     user: &mut GooseUser,
 ) -> TransactionResult {
     let index = counter.fetch_add(1, Ordering::Relaxed);
-    if index < pool.len() {
-        let sbom_id = &pool[index];
-        let _response = user.delete(&format!("/api/v3/sbom/{sbom_id}")).await?;
+    let Some(sbom_id) = pool.get(index) else {
+        if index == pool.len() {
+            log::warn!("All {} SBOMs of the delete pool are deleted", pool.len());
+        }
+        return Err(Box::new(TransactionError::Custom(format!(
+            "delete pool exhausted, all {} SBOMs are deleted",
+            pool.len()
+        ))));
+    };
+
+    let response = request::delete(
+        user,
+        &format!("/api/v3/sbom/{}", encode(sbom_id)),
+        "/api/v3/sbom/{id}",
+    )
+    .await?;
+
+    verify(user, response, &Assertions::default()).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn copy_spdx() {
+        let original = json!({
+            "spdxVersion": "SPDX-2.3",
+            "documentNamespace": "https://example.com/sbom",
+            "packages": [],
+        });
+        let copy = unique_copy(&original.to_string()).expect("must copy");
+        assert_ne!(copy["documentNamespace"], original["documentNamespace"]);
+        assert_eq!(copy["packages"], original["packages"]);
+    }
+
+    #[test]
+    fn copy_cyclonedx() {
+        let original = json!({
+            "bomFormat": "CycloneDX",
+            "serialNumber": "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79",
+        });
+        let copy = unique_copy(&original.to_string()).expect("must copy");
+        assert_ne!(copy["serialNumber"], original["serialNumber"]);
+    }
+
+    #[test]
+    fn copy_unknown() {
+        unique_copy(r#"{"foo": "bar"}"#).expect_err("must fail");
+        unique_copy("<bom/>").expect_err("must fail");
     }
-    Ok(())
 }
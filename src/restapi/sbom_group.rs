@@ -1,15 +1,15 @@
+use super::request;
+use crate::assertion::{Assertions, verify};
 use goose::goose::{GooseUser, TransactionResult};
 
 pub async fn get_sbom_group(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/group/sbom/{id}")).await?;
+    let response = request::get(user, &format!("/api/v3/group/sbom/{id}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn get_sbom_group_assignments(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/group/sbom-assignment/{id}"))
-        .await?;
+    let response = request::get(user, &format!("/api/v3/group/sbom-assignment/{id}")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
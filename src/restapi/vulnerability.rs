@@ -1,46 +1,51 @@
+use super::request;
+use crate::assertion::{Assertions, JsonCheck, verify};
 use goose::goose::{GooseUser, TransactionResult};
 use serde_json::json;
 
 pub async fn get_vulnerability(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get(&format!("/api/v3/vulnerability/{id}")).await?;
-
-    Ok(())
+    let response = request::get(user, &format!("/api/v3/vulnerability/{id}")).await?;
+
+    verify(
+        user,
+        response,
+        &Assertions::json().check(JsonCheck::exists("/identifier")),
+    )
+    .await
 }
 
 pub async fn get_vulnerability_scores(id: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .get(&format!("/api/v3/vulnerability/{id}?scores=true"))
-        .await?;
+    let response = request::get(user, &format!("/api/v3/vulnerability/{id}?scores=true")).await?;
 
-    Ok(())
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn post_vulnerability_analyze(purl: String, user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .post_json(
-            "/api/v3/vulnerability/analyze",
-            &json!({
-                "purls": [
-                     purl
-                ]
-            }),
-        )
-        .await?;
-
-    Ok(())
+    let response = request::post_json(
+        user,
+        "/api/v3/vulnerability/analyze",
+        &json!({
+            "purls": [
+                 purl
+            ]
+        }),
+    )
+    .await?;
+
+    verify(user, response, &Assertions::json()).await
 }
 
 pub async fn post_vulnerability_analyze_v3(user: &mut GooseUser) -> TransactionResult {
-    let _response = user
-        .post_json(
-            "/api/v3/vulnerability/analyze",
-            &json!({
-                "purls": [
-                    "pkg:rpm/redhat/openssl@3.0.0"
-                ]
-            }),
-        )
-        .await?;
-
-    Ok(())
+    let response = request::post_json(
+        user,
+        "/api/v3/vulnerability/analyze",
+        &json!({
+            "purls": [
+                "pkg:rpm/redhat/openssl@3.0.0"
+            ]
+        }),
+    )
+    .await?;
+
+    verify(user, response, &Assertions::json()).await
 }
@@ -0,0 +1,392 @@
+use crate::{
+    oidc::OpenIdTokenProvider,
+    scenario::{Candidates, Scenario},
+    utils::DisplayVec,
+};
+use anyhow::{Context, bail};
+use reqwest_12::{Client, RequestBuilder};
+use serde_json::{Value, json};
+use urlencoding::encode;
+
+/// The maximum number of SBOMs in the delete pool, matching the database evaluation
+const DELETE_POOL_SIZE: usize = 100;
+
+/// The maximum number of PURLs to request recommendations for
+const RECOMMENDATIONS_SIZE: usize = 128;
+
+/// The number of PURLs to analyze with a single request
+const ANALYZE_BATCH_SIZE: usize = 128;
+
+impl Scenario {
+    /// Discover a scenario using the REST API of a trustify instance
+    ///
+    /// Every entry gets a pool of up to `size` candidates. Entries without candidates are disabled.
+    pub async fn discover(
+        host: String,
+        provider: Option<OpenIdTokenProvider>,
+        size: usize,
+    ) -> anyhow::Result<Self> {
+        let discovery = Discovery::new(host, provider, size);
+
+        let large_sboms = discovery.large_sboms().await?;
+        let deletable_sboms = large_sboms.iter().map(|(id, _)| id.clone()).collect();
+        let (large_sbom_ids, large_sbom_digests): (Vec<_>, Vec<_>) =
+            large_sboms.into_iter().take(size).unzip();
+
+        let mut purls = vec![];
+        let mut purl_ids = vec![];
+        if let Some(id) = large_sbom_ids.first() {
+            for (purl, purl_id) in optional(discovery.sbom_purls(id).await) {
+                purls.push(purl);
+                purl_ids.push(purl_id);
+            }
+        }
+
+        Ok(Candidates {
+            large_sbom_ids,
+            large_sbom_digests,
+            max_vuln: optional(discovery.max_vuln().await),
+            sbom_purl: purls.iter().take(size).cloned().collect(),
+            analyze_purl: optional(discovery.analysis_purl(&purls).await),
+            purl_details: purl_ids.into_iter().take(size).collect(),
+            recommendations: recommendations(&purls),
+            deletable_sboms,
+            advisory: optional(discovery.advisory().await),
+            sbom_group: optional(discovery.sbom_group().await),
+            product: optional(discovery.product().await),
+            organization: optional(discovery.organization().await),
+            base_purl: optional(discovery.base_purl().await),
+            importer: optional(discovery.importer().await),
+        }
+        .into())
+    }
+}
+
+/// Log a failed discovery and continue without candidates
+fn optional<T>(result: anyhow::Result<Vec<T>>) -> Vec<T> {
+    result.unwrap_or_else(|err| {
+        log::warn!("Unable to discover candidates, disabling entry: {err:#}");
+        vec![]
+    })
+}
+
+/// Discovers scenario candidates using the REST API of a trustify instance.
+pub(crate) struct Discovery {
+    client: Client,
+    host: String,
+    provider: Option<OpenIdTokenProvider>,
+    /// The maximum number of candidates to find
+    size: usize,
+}
+
+impl Discovery {
+    pub fn new(host: String, provider: Option<OpenIdTokenProvider>, size: usize) -> Self {
+        Self {
+            client: Client::new(),
+            host,
+            provider,
+            size,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<Value> {
+        let request = match &self.provider {
+            Some(provider) => provider.authorize(request).await?,
+            None => request,
+        };
+
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.host.trim_end_matches('/'))
+    }
+
+    async fn get(&self, path: &str) -> anyhow::Result<Value> {
+        self.send(self.client.get(self.url(path)))
+            .await
+            .with_context(|| format!("failed to get {path}"))
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> anyhow::Result<Value> {
+        self.send(self.client.post(self.url(path)).json(body))
+            .await
+            .with_context(|| format!("failed to post {path}"))
+    }
+
+    /// Get the items of a list response
+    async fn items(&self, path: &str) -> anyhow::Result<Vec<Value>> {
+        Ok(items(self.get(path).await?))
+    }
+
+    /// Get the SBOMs with the most packages, as pairs of ID and SHA256 digest
+    pub async fn large_sboms(&self) -> anyhow::Result<Vec<(String, String)>> {
+        let limit = self.size.max(DELETE_POOL_SIZE);
+        let mut items = match self
+            .items(&format!(
+                "/api/v3/sbom?sort=number_of_packages:desc&limit={limit}"
+            ))
+            .await
+        {
+            Ok(items) => items,
+            Err(err) => {
+                log::warn!("Unable to sort SBOMs by number of packages, sorting locally: {err:#}");
+                self.items("/api/v3/sbom?limit=1000").await?
+            }
+        };
+
+        items.sort_by_key(|item| {
+            std::cmp::Reverse(item["number_of_packages"].as_u64().unwrap_or_default())
+        });
+
+        let result: Vec<_> = items
+            .iter()
+            .filter_map(|item| Some((uuid(item["id"].as_str()?), sbom_digest(item)?)))
+            .take(limit)
+            .collect();
+
+        if result.is_empty() {
+            bail!("function large_sboms: no SBOMs found");
+        }
+
+        Ok(result)
+    }
+
+    /// Vulnerabilities, referenced by a lot of advisories
+    pub async fn max_vuln(&self) -> anyhow::Result<Vec<String>> {
+        let mut items = self.items("/api/v3/vulnerability?limit=1000").await?;
+        items.sort_by_key(|item| {
+            std::cmp::Reverse(
+                item["advisories"]
+                    .as_array()
+                    .map(Vec::len)
+                    .unwrap_or_default(),
+            )
+        });
+
+        self.take(strings(&items, "/identifier"), "max_vuln")
+    }
+
+    /// Package PURLs of an SBOM, as pairs of PURL and PURL ID
+    pub async fn sbom_purls(&self, sbom_id: &str) -> anyhow::Result<Vec<(String, String)>> {
+        let items = self
+            .items(&format!(
+                "/api/v3/sbom/{}/packages?limit=1000",
+                encode(&format!("urn:uuid:{sbom_id}"))
+            ))
+            .await?;
+
+        Ok(items
+            .iter()
+            .flat_map(|item| item["purl"].as_array().cloned().unwrap_or_default())
+            .filter_map(|purl| {
+                Some((
+                    purl["purl"].as_str()?.to_string(),
+                    uuid(purl["uuid"].as_str()?),
+                ))
+            })
+            .collect())
+    }
+
+    /// PURLs affected by vulnerabilities, out of the provided ones
+    pub async fn analysis_purl(&self, purls: &[String]) -> anyhow::Result<Vec<String>> {
+        let mut result = vec![];
+
+        for batch in purls.chunks(ANALYZE_BATCH_SIZE) {
+            let response = self
+                .post("/api/v3/vulnerability/analyze", &json!({ "purls": batch }))
+                .await?;
+            let Value::Object(response) = response else {
+                continue;
+            };
+            result.extend(
+                batch
+                    .iter()
+                    .filter(|purl| response.get(*purl).is_some_and(has_findings))
+                    .cloned(),
+            );
+            if result.len() >= self.size {
+                break;
+            }
+        }
+
+        self.take(result, "analysis_purl")
+    }
+
+    /// Advisory IDs, most recently modified first
+    pub async fn advisory(&self) -> anyhow::Result<Vec<String>> {
+        let items = self
+            .items(&format!(
+                "/api/v3/advisory?sort=modified:desc&limit={}",
+                self.size
+            ))
+            .await?;
+        let ids = strings(&items, "/uuid").iter().map(|id| uuid(id)).collect();
+        self.take(ids, "advisory")
+    }
+
+    /// Values of the first items of a list endpoint
+    async fn list(&self, path: &str, pointer: &str) -> anyhow::Result<Vec<String>> {
+        let items = self.items(&format!("{path}?limit={}", self.size)).await?;
+        self.take(strings(&items, pointer), path)
+    }
+
+    /// Limit the candidates to the requested size, errors when there are none
+    fn take(&self, mut values: Vec<String>, name: &str) -> anyhow::Result<Vec<String>> {
+        values.truncate(self.size);
+        if values.is_empty() {
+            bail!("{name}: no candidates found");
+        }
+        Ok(values)
+    }
+
+    pub async fn sbom_group(&self) -> anyhow::Result<Vec<String>> {
+        self.list("/api/v3/group/sbom", "/id").await
+    }
+
+    pub async fn product(&self) -> anyhow::Result<Vec<String>> {
+        self.list("/api/v3/product", "/id").await
+    }
+
+    pub async fn organization(&self) -> anyhow::Result<Vec<String>> {
+        self.list("/api/v3/organization", "/id").await
+    }
+
+    pub async fn base_purl(&self) -> anyhow::Result<Vec<String>> {
+        self.list("/api/v3/purl/base", "/purl").await
+    }
+
+    pub async fn importer(&self) -> anyhow::Result<Vec<String>> {
+        // the importer endpoint is not paginated
+        let items = self.items("/api/v3/importer").await?;
+        self.take(strings(&items, "/name"), "importer")
+    }
+}
+
+/// The items of a list response, which is either paginated or a plain array
+fn items(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        mut value => match value.get_mut("items").map(Value::take) {
+            Some(Value::Array(items)) => items,
+            _ => vec![],
+        },
+    }
+}
+
+/// All string values found at the JSON pointer of the items
+fn strings(items: &[Value], pointer: &str) -> Vec<String> {
+    items
+        .iter()
+        .filter_map(|item| item.pointer(pointer)?.as_str())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Strip the `urn:uuid:` prefix, if present
+fn uuid(id: &str) -> String {
+    id.strip_prefix("urn:uuid:").unwrap_or(id).to_string()
+}
+
+/// The SHA256 digest of an SBOM, prefixed with `sha256:`
+fn sbom_digest(item: &Value) -> Option<String> {
+    if let Some(hashes) = item["hashes"].as_array() {
+        return hashes
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|hash| hash.starts_with("sha256:"))
+            .map(ToString::to_string);
+    }
+
+    let sha256 = item["sha256"].as_str()?;
+    Some(match sha256.starts_with("sha256:") {
+        true => sha256.to_string(),
+        false => format!("sha256:{sha256}"),
+    })
+}
+
+/// Check if an analysis result contains any findings
+pub(crate) fn has_findings(value: &Value) -> bool {
+    match value {
+        Value::Array(values) => !values.is_empty(),
+        Value::Object(values) => values.values().any(has_findings),
+        _ => false,
+    }
+}
+
+/// Check if the PURL version ends with a Red Hat build suffix (`redhat-[0-9]+`)
+fn is_redhat_build(purl: &str) -> bool {
+    let version = purl
+        .split_once('@')
+        .map(|(_, version)| version.split(['?', '#']).next().unwrap_or(version))
+        .unwrap_or_default();
+
+    version
+        .rsplit_once("redhat-")
+        .is_some_and(|(_, build)| !build.is_empty() && build.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Collect the PURLs eligible for recommendations
+fn recommendations(purls: &[String]) -> Option<DisplayVec<String>> {
+    let purls: Vec<_> = purls
+        .iter()
+        .filter(|purl| is_redhat_build(purl))
+        .take(RECOMMENDATIONS_SIZE)
+        .cloned()
+        .collect();
+    (!purls.is_empty()).then_some(DisplayVec(purls))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paginated_items() {
+        let items = items(json!({"items": [{"id": "a"}, {"id": "b"}], "total": 2}));
+        assert_eq!(strings(&items, "/id"), ["a", "b"]);
+    }
+
+    #[test]
+    fn plain_items() {
+        let items = items(json!([{"name": "a"}, {"other": "b"}]));
+        assert_eq!(strings(&items, "/name"), ["a"]);
+    }
+
+    #[test]
+    fn strip_uuid() {
+        assert_eq!(uuid("urn:uuid:0193"), "0193");
+        assert_eq!(uuid("0193"), "0193");
+    }
+
+    #[test]
+    fn digest() {
+        assert_eq!(
+            sbom_digest(&json!({"hashes": ["sha384:x", "sha256:abc"]})).as_deref(),
+            Some("sha256:abc")
+        );
+        assert_eq!(
+            sbom_digest(&json!({"sha256": "abc"})).as_deref(),
+            Some("sha256:abc")
+        );
+        assert_eq!(sbom_digest(&json!({})), None);
+    }
+
+    #[test]
+    fn findings() {
+        assert!(has_findings(&json!({"details": [{"id": "CVE-1"}]})));
+        assert!(!has_findings(&json!({"details": []})));
+        assert!(!has_findings(&json!([])));
+    }
+
+    #[test]
+    fn redhat_build() {
+        assert!(is_redhat_build("pkg:generic/tomcat@9.0.43.redhat-00011"));
+        assert!(is_redhat_build(
+            "pkg:maven/io.vertx/vertx-grpc@4.4.4.redhat-00001?type=jar"
+        ));
+        assert!(!is_redhat_build("pkg:rpm/redhat/openssl@3.0.0"));
+        assert!(!is_redhat_build("pkg:generic/foo@1.0.redhat-"));
+        assert!(!is_redhat_build("pkg:generic/foo"));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{
+    scenario::{Candidates, Scenario, purl::CanonicalPurl},
+    utils::DisplayVec,
+};
+use anyhow::{Context, bail};
+use serde_json::Value;
+use sqlx::{Row, postgres::PgRow};
+
+impl Scenario {
+    /// Evaluate a scenario from the database
+    ///
+    /// Every entry gets a pool of up to `size` candidates, ordered by relevance.
+    pub async fn eval(db: &str, size: usize) -> anyhow::Result<Self> {
+        let loader = Loader::new(db.to_string(), size);
+
+        let (large_sbom_ids, large_sbom_digests) = loader.large_sbom().await?.into_iter().unzip();
+
+        Ok(Candidates {
+            large_sbom_ids,
+            large_sbom_digests,
+            max_vuln: loader.max_vuln().await?,
+            sbom_purl: loader.sbom_purl().await?,
+            analyze_purl: loader.analysis_purl().await?,
+            purl_details: loader.purl_details().await?,
+            recommendations: loader.purl_with_recommendations().await.ok(),
+            deletable_sboms: loader.deletable_sboms().await?,
+            advisory: loader.download_advisory().await?,
+            sbom_group: loader.sbom_group().await.unwrap_or_default(),
+            product: loader.product().await.unwrap_or_default(),
+            organization: loader.organization().await.unwrap_or_default(),
+            base_purl: loader.base_purl().await.unwrap_or_default(),
+            importer: loader.importer().await.unwrap_or_default(),
+        }
+        .into())
+    }
+}
+
+struct Loader {
+    db: String,
+    /// The maximum number of candidates to find
+    size: usize,
+}
+
+impl Loader {
+    pub fn new(db: String, size: usize) -> Self {
+        Self { db, size }
+    }
+
+    /// Find rows using [`Self::find_rows`] and return the column `"result"`.
+    async fn find(&self, sql: &str) -> anyhow::Result<Vec<String>> {
+        self.find_rows(sql, self.size)
+            .await?
+            .iter()
+            .map(|row| Ok(row.try_get("result")?))
+            .collect()
+    }
+
+    /// Find rows, binding `limit` as `$1`. Errors when none was found.
+    async fn find_rows(&self, sql: &str, limit: usize) -> anyhow::Result<Vec<PgRow>> {
+        let mut db = crate::db::connect(&self.db).await?;
+
+        let rows = sqlx::query(sql)
+            .bind(i64::try_from(limit)?)
+            .fetch_all(&mut db)
+            .await?;
+
+        if rows.is_empty() {
+            bail!("no matching row found in database query");
+        }
+
+        Ok(rows)
+    }
+
+    /// Find rows using [`Self::find_rows`] and convert the JSON column `"result"` into purls.
+    ///
+    /// Duplicates are removed, keeping the order of the rows.
+    async fn find_purls(&self, sql: &str, limit: usize) -> anyhow::Result<Vec<String>> {
+        let mut result = Vec::new();
+        for row in self.find_rows(sql, limit).await? {
+            let value: Value = row.try_get("result")?;
+            let purl: CanonicalPurl = serde_json::from_value(value)?;
+            let purl = purl.to_string();
+            if !result.contains(&purl) {
+                result.push(purl);
+            }
+        }
+        Ok(result)
+    }
+
+    /// get the ID and SHA256 of the largest SBOMs (by number of packages)
+    pub async fn large_sbom(&self) -> anyhow::Result<Vec<(String, String)>> {
+        // get the largest SBOMs in the database
+        let rows = self
+            .find_rows(
+                r#"
+select
+    b.sbom_id::text as id,
+    concat('sha256:', c.sha256) as sha,
+    count(b.node_id) as num
+from sbom a
+     join sbom_node b on a.sbom_id = b.sbom_id
+     join source_document c on a.source_document_id = c.id
+group by
+    b.sbom_id,
+    c.sha256
+order by
+    num desc
+limit $1
+"#,
+                self.size,
+            )
+            .await
+            .context("function large_sbom: no SBOMs found in database")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("id"), row.get("sha")))
+            .collect())
+    }
+
+    /// Vulnerabilities, referenced by a lot of advisories
+    pub async fn max_vuln(&self) -> anyhow::Result<Vec<String>> {
+        self.find(
+            r#"
+select
+    a.id as result,
+    count(b.vulnerability_id) as num
+from vulnerability a
+     join advisory_vulnerability b on a.id = b.vulnerability_id
+group by
+    a.id
+order by num desc
+limit $1
+"#,
+        )
+        .await
+        .context("function max_vuln: no vulnerabilities found in database")
+    }
+
+    /// Purls of SBOM packages
+    pub async fn sbom_purl(&self) -> anyhow::Result<Vec<String>> {
+        self.find_purls(
+            r#"
+select
+    b.purl as result
+from
+    sbom_package_purl_ref a
+    left join qualified_purl b on a.qualified_purl_id = b.id
+limit $1
+"#,
+            self.size,
+        )
+        .await
+        .context("function sbom_purl: no SBOM packages found in database")
+    }
+
+    /// Purls with vulnerabilities
+    pub async fn analysis_purl(&self) -> anyhow::Result<Vec<String>> {
+        self.find_purls(
+            r#"
+select distinct
+    d.vulnerability_id,
+    d.advisory_id,
+    a.purl as result
+from
+    qualified_purl a
+    left join versioned_purl b on a.versioned_purl_id = b.id
+    left join base_purl c on b.base_purl_id = c.id
+    inner join purl_status d on d.base_purl_id = c.id
+    inner join status e on e.id = d.status_id
+    inner join version_range f on d.version_range_id = f.id
+where
+    e.slug = 'affected'
+and
+    version_matches(b.version, f.*) = TRUE
+order by
+    vulnerability_id
+limit $1
+"#,
+            self.size,
+        )
+        .await
+        .context("function analysis_purl: no affected PURLs found in database")
+    }
+
+    /// Purl IDs for details lookup
+    pub async fn purl_details(&self) -> anyhow::Result<Vec<String>> {
+        self.find(
+            r#"
+SELECT
+    spr.qualified_purl_id::text AS result,
+    COUNT(DISTINCT spl.license_id) AS license_count
+FROM
+    sbom_package_purl_ref spr
+JOIN
+    sbom_package sp ON spr.sbom_id = sp.sbom_id AND spr.node_id = sp.node_id
+LEFT JOIN
+    sbom_package_license spl ON sp.sbom_id = spl.sbom_id AND sp.node_id = spl.node_id
+GROUP BY
+    spr.qualified_purl_id
+ORDER BY
+    license_count DESC
+LIMIT $1;
+"#,
+        )
+        .await
+        .context("function purl_details: no PURLs found in database")
+    }
+
+    // A purl whose version matches redhat-[0-9]+$ regex
+    pub async fn purl_with_recommendations(&self) -> anyhow::Result<DisplayVec<String>> {
+        self.find_purls(
+            r#"
+SELECT
+    purl AS result
+FROM
+    qualified_purl
+WHERE
+    purl->>'version' ~ 'redhat-[0-9]+$'
+LIMIT $1;
+"#,
+            128,
+        )
+        .await
+        .map(DisplayVec)
+    }
+
+    /// Get a pool of deletable SBOMs (up to 100)
+    /// These SBOMs are selected based on having the most packages
+    pub async fn deletable_sboms(&self) -> anyhow::Result<Vec<String>> {
+        let mut db = crate::db::connect(&self.db).await?;
+
+        let rows = sqlx::query(
+            r#"
+SELECT
+    a.sbom_id::text as id,
+    count(b.*) as package_count
+FROM
+    sbom a
+    JOIN sbom_package b ON a.sbom_id = b.sbom_id
+GROUP BY
+    a.sbom_id
+ORDER BY
+    package_count DESC,
+    a.sbom_id
+LIMIT 100
+"#,
+        )
+        .fetch_all(&mut db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| row.get::<String, _>("id"))
+            .collect())
+    }
+
+    /// Advisory IDs for download and query the advisory details
+    pub async fn download_advisory(&self) -> anyhow::Result<Vec<String>> {
+        self.find(
+            r#"
+SELECT id::text as result
+FROM public.advisory order by modified desc limit $1;"#,
+        )
+        .await
+        .context("function download_advisory: no advisories found in database")
+    }
+
+    /// SBOM group UUIDs
+    pub async fn sbom_group(&self) -> anyhow::Result<Vec<String>> {
+        self.find("SELECT id::text as result FROM sbom_group LIMIT $1")
+            .await
+            .context("function sbom_group: no SBOM groups found in database")
+    }
+
+    /// Product UUIDs
+    pub async fn product(&self) -> anyhow::Result<Vec<String>> {
+        self.find("SELECT id::text as result FROM product LIMIT $1")
+            .await
+            .context("function product: no products found in database")
+    }
+
+    /// Organization UUIDs
+    pub async fn organization(&self) -> anyhow::Result<Vec<String>> {
+        self.find("SELECT id::text as result FROM organization LIMIT $1")
+            .await
+            .context("function organization: no organizations found in database")
+    }
+
+    /// Base PURL keys (type:namespace/name or type:name)
+    pub async fn base_purl(&self) -> anyhow::Result<Vec<String>> {
+        self.find(
+            r#"
+SELECT
+    CASE
+        WHEN namespace IS NOT NULL AND namespace != ''
+        THEN 'pkg:' || type || '/' || namespace || '/' || name
+        ELSE 'pkg:' || type || '/' || name
+    END as result
+FROM base_purl
+LIMIT $1"#,
+        )
+        .await
+        .context("function base_purl: no base PURLs found in database")
+    }
+
+    /// Importer names
+    pub async fn importer(&self) -> anyhow::Result<Vec<String>> {
+        self.find("SELECT name as result FROM importer LIMIT $1")
+            .await
+            .context("function importer: no importers found in database")
+    }
+}
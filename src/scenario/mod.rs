@@ -1,22 +1,24 @@
-mod purl;
+mod discover;
+#[cfg(feature = "postgres")]
+mod loader;
+mod pool;
+pub mod purl;
+pub mod validate;
 
-use crate::{scenario::purl::CanonicalPurl, utils::DisplayVec};
-use anyhow::{Context, anyhow};
-use serde_json::Value;
-use sqlx::{Executor, Row, postgres::PgRow};
+pub use pool::{Pool, Select, Selector, Strategy};
+
+use crate::utils::DisplayVec;
+use anyhow::Context;
 use std::io::BufReader;
 
 /// implement to that we can explicitly state what we want
 mod required {
-    use serde::{
-        Deserialize, Deserializer, Serializer,
-        de::{Error, Visitor},
-    };
-    use std::fmt::Formatter;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-    pub fn serialize<S>(value: &Option<String>, s: S) -> Result<S::Ok, S::Error>
+    pub fn serialize<S, T>(value: &Option<T>, s: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
+        T: Serialize,
     {
         match value {
             None => s.serialize_none(),
@@ -24,73 +26,44 @@ mod required {
         }
     }
 
-    pub fn deserialize<'de, D>(d: D) -> Result<Option<String>, D::Error>
+    /// Unlike a plain `Option`, the field must be present, but may be `null` to deactivate it.
+    pub fn deserialize<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
     where
         D: Deserializer<'de>,
+        T: Deserialize<'de>,
     {
-        struct De;
-
-        impl<'de> Visitor<'de> for De {
-            type Value = Option<String>;
-
-            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-                formatter.write_str("field must be present, but may be 'null' to deactivate")
-            }
-
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-            where
-                E: Error,
-            {
-                Ok(Some(v.to_string()))
-            }
-
-            fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-            where
-                D: Deserializer<'de>,
-            {
-                Ok(Some(String::deserialize(deserializer)?))
-            }
-
-            fn visit_none<E>(self) -> Result<Self::Value, E>
-            where
-                E: Error,
-            {
-                Ok(None)
-            }
-        }
-
-        d.deserialize_option(De)
+        Option::<T>::deserialize(d)
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
 pub(crate) struct Scenario {
     #[serde(with = "required")]
-    pub get_sbom: Option<String>,
+    pub get_sbom: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_sbom_advisories: Option<String>,
+    pub get_sbom_advisories: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_sbom_packages: Option<String>,
+    pub get_sbom_packages: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_sbom_related: Option<String>,
+    pub get_sbom_related: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_vulnerability: Option<String>,
+    pub get_vulnerability: Option<Pool>,
 
     #[serde(with = "required")]
-    pub sbom_by_package: Option<String>,
+    pub sbom_by_package: Option<Pool>,
 
     #[serde(with = "required")]
-    pub sbom_license_ids: Option<String>,
+    pub sbom_license_ids: Option<Pool>,
 
     #[serde(with = "required")]
-    pub analyze_purl: Option<String>,
+    pub analyze_purl: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_purl_details: Option<String>,
+    pub get_purl_details: Option<Pool>,
 
     pub get_recommendations: Option<DisplayVec<String>>,
 
@@ -98,389 +71,182 @@ pub(crate) struct Scenario {
     pub delete_sbom_pool: Option<Vec<String>>,
 
     #[serde(with = "required")]
-    pub download_advisory: Option<String>,
+    pub download_advisory: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_advisory: Option<String>,
+    pub get_advisory: Option<Pool>,
 
     #[serde(with = "required")]
-    pub download_sbom: Option<String>,
+    pub download_sbom: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_sbom_license_export: Option<String>,
+    pub get_sbom_license_export: Option<Pool>,
 
     #[serde(with = "required")]
-    pub count_sbom_by_package: Option<String>,
+    pub count_sbom_by_package: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_sbom_group: Option<String>,
+    pub get_sbom_group: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_product: Option<String>,
+    pub get_product: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_organization: Option<String>,
+    pub get_organization: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_base_purl: Option<String>,
+    pub get_base_purl: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_analysis_component: Option<String>,
+    pub get_analysis_component: Option<Pool>,
 
     #[serde(with = "required")]
-    pub render_sbom_graph: Option<String>,
+    pub render_sbom_graph: Option<Pool>,
+
+    /// Component names to search the analysis graph for, defaults to the names of the packages of the scenario
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_analysis_component: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_importer: Option<String>,
+    pub get_importer: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_weakness: Option<String>,
+    pub get_weakness: Option<Pool>,
 
     #[serde(with = "required")]
-    pub get_spdx_license: Option<String>,
+    pub get_spdx_license: Option<Pool>,
 }
 
 impl Scenario {
-    /// Load a scenario file, or evaluate one
-    pub async fn load(scenario_file: Option<&str>) -> anyhow::Result<Self> {
-        if let Some(scenario_file) = scenario_file {
-            Ok(serde_json5::from_reader(BufReader::new(
-                std::fs::File::open(scenario_file)
-                    .with_context(|| format!("opening scenario file: {scenario_file}"))?,
-            ))
-            .context("reading scenario file")?)
-        } else {
-            Self::eval().await
-        }
+    /// Load a scenario file
+    pub fn load(scenario_file: &str) -> anyhow::Result<Self> {
+        serde_json5::from_reader(BufReader::new(
+            std::fs::File::open(scenario_file)
+                .with_context(|| format!("opening scenario file: {scenario_file}"))?,
+        ))
+        .context("reading scenario file")
     }
 
-    pub async fn eval() -> anyhow::Result<Self> {
-        let db = std::env::var("DATABASE_URL")
-            .map_err(|err| anyhow!("failed to get database URL from `DATABASE_URL`: {err}"))?;
-
-        let loader = Loader::new(db);
-
-        let large_sbom = loader.large_sbom().await?;
-        let large_sbom_id = Some(large_sbom.0);
-        let large_sbom_digest = Some(large_sbom.1);
-        let max_vuln = Some(loader.max_vuln().await?);
-        let sbom_purl = Some(loader.sbom_purl().await?);
-        let sbom_license_ids = large_sbom_id.clone().map(|id| format!("urn:uuid:{id}"));
-        let analyze_purl = Some(loader.analysis_purl().await?);
-        let get_purl_details = Some(loader.purl_details().await?);
-        let recommendations_purl = Some(loader.purl_with_recommendations().await?);
-        let delete_sbom_pool = Some(
-            loader
-                .deletable_sboms()
-                .await?
-                .iter()
-                .map(|sbom_id| format!("urn:uuid:{sbom_id}"))
-                .collect(),
-        );
-        let download_advisory = Some(loader.download_advisory().await?);
-        let get_advisory = Some(loader.download_advisory().await?);
-
-        let get_sbom_group = loader.sbom_group().await.ok();
-        let get_product = loader.product().await.ok();
-        let get_organization = loader.organization().await.ok();
-        let get_base_purl = loader.base_purl().await.ok();
-        let get_importer = loader.importer().await.ok();
-
-        Ok(Self {
-            get_sbom: large_sbom_digest.clone(),
-            get_sbom_advisories: large_sbom_digest.clone(),
-            get_sbom_related: large_sbom_id.as_ref().map(|id| format!("uri:uuid:{id}")),
-            get_sbom_packages: large_sbom_id.as_ref().map(|id| format!("uri:uuid:{id}")),
-
-            get_vulnerability: max_vuln,
-
-            sbom_by_package: sbom_purl.clone(),
-            sbom_license_ids,
-            analyze_purl,
-            get_purl_details,
-            get_recommendations: recommendations_purl,
-            delete_sbom_pool,
-            download_advisory,
-            get_advisory,
-
-            download_sbom: large_sbom_digest.clone(),
-            get_sbom_license_export: large_sbom_id.as_ref().map(|id| format!("urn:uuid:{id}")),
-            count_sbom_by_package: sbom_purl,
-            get_sbom_group,
-            get_product,
-            get_organization,
-            get_base_purl,
-            get_analysis_component: large_sbom_digest,
-            render_sbom_graph: large_sbom_id.as_ref().map(|id| format!("urn:uuid:{id}")),
-            get_importer,
-            get_weakness: Some("CWE-79".to_string()),
-            get_spdx_license: Some("MIT".to_string()),
-        })
-    }
-}
-
-struct Loader {
-    db: String,
-}
-
-impl Loader {
-    pub fn new(db: String) -> Self {
-        Self { db }
+    /// All package URLs used by the scenario, without duplicates.
+    pub fn purls(&self) -> Vec<String> {
+        let mut result: Vec<String> = [
+            &self.sbom_by_package,
+            &self.analyze_purl,
+            &self.count_sbom_by_package,
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(|pool| pool.values())
+        .chain(self.get_recommendations.iter().flat_map(|purls| &purls.0))
+        .cloned()
+        .collect();
+
+        result.sort_unstable();
+        result.dedup();
+        result
     }
 
-    /// Find a row using [`Self::find_row`] and return the column `"result"`.
-    async fn find(&self, sql: &str) -> anyhow::Result<String> {
-        Ok(self.find_row(sql).await?.get("result"))
+    /// The component names searched by the analysis transactions.
+    ///
+    /// Scenario files created before the entry existed fall back to the names of the packages they use.
+    pub fn analysis_component_names(&self) -> Option<Pool> {
+        self.search_analysis_component
+            .clone()
+            .or_else(|| Pool::new(component_names(&self.purls())))
     }
+}
 
-    /// Find a row, errors when none was found
-    async fn find_row(&self, sql: &str) -> anyhow::Result<PgRow> {
-        let mut db = crate::db::connect(&self.db).await?;
-
-        db.fetch_optional(sql)
-            .await?
-            .ok_or_else(|| anyhow!("no matching row found in database query"))
+/// The names of the packages, without duplicates, in order of first appearance. Invalid purls are skipped.
+fn component_names<'a>(purls: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for purl in purls {
+        if let Ok(purl) = purl.parse::<purl::CanonicalPurl>()
+            && !result.contains(&purl.name)
+        {
+            result.push(purl.name);
+        }
     }
+    result
+}
 
-    /// Find all rows
-    async fn find_rows(&self, sql: &str) -> anyhow::Result<Vec<PgRow>> {
-        let mut db = crate::db::connect(&self.db).await?;
-
-        Ok(db.fetch_all(sql).await?)
-    }
+/// Candidates for the entries of a scenario, ordered by relevance.
+#[derive(Default)]
+struct Candidates {
+    /// IDs (without `urn:uuid:` prefix) of the largest SBOMs
+    large_sbom_ids: Vec<String>,
+    /// SHA256 digests (with `sha256:` prefix) of the largest SBOMs
+    large_sbom_digests: Vec<String>,
+    max_vuln: Vec<String>,
+    sbom_purl: Vec<String>,
+    analyze_purl: Vec<String>,
+    purl_details: Vec<String>,
+    recommendations: Option<DisplayVec<String>>,
+    /// IDs (without `urn:uuid:` prefix) of SBOMs which may be deleted
+    deletable_sboms: Vec<String>,
+    advisory: Vec<String>,
+    sbom_group: Vec<String>,
+    product: Vec<String>,
+    organization: Vec<String>,
+    base_purl: Vec<String>,
+    importer: Vec<String>,
+}
 
-    /// get the SHA256 of the largest SBOM (by number of packages)
-    pub async fn large_sbom(&self) -> anyhow::Result<(String, String)> {
-        // get the largest SBOM in the database
-        let row = self
-            .find_row(
-                r#"
-select
-    b.sbom_id::text as id,
-    concat('sha256:', c.sha256) as sha,
-    count(b.node_id) as num
-from sbom a
-     join sbom_node b on a.sbom_id = b.sbom_id
-     join source_document c on a.source_document_id = c.id
-group by
-    b.sbom_id,
-    c.sha256
-order by
-    num desc
-limit 1
-"#,
+impl From<Candidates> for Scenario {
+    fn from(candidates: Candidates) -> Self {
+        let large_sbom_digest = Pool::new(candidates.large_sbom_digests);
+        let search_analysis_component = Pool::new(component_names(
+            candidates.sbom_purl.iter().chain(&candidates.analyze_purl),
+        ));
+        let sbom_purl = Pool::new(candidates.sbom_purl);
+        let advisory = Pool::new(candidates.advisory);
+
+        let large_sbom_id = |prefix: &str| {
+            Pool::new(
+                candidates
+                    .large_sbom_ids
+                    .iter()
+                    .map(|id| format!("{prefix}{id}"))
+                    .collect(),
             )
-            .await
-            .context("function large_sbom: no SBOMs found in database")?;
-
-        Ok((row.get("id"), row.get("sha")))
-    }
-
-    /// A vulnerability, referenced by a lot of advisories
-    pub async fn max_vuln(&self) -> anyhow::Result<String> {
-        self.find(
-            r#"
-select
-    a.id as result,
-    count(b.vulnerability_id) as num
-from vulnerability a
-     join advisory_vulnerability b on a.id = b.vulnerability_id
-group by
-    a.id
-order by num desc
-"#,
-        )
-        .await
-        .context("function max_vuln: no vulnerabilities found in database")
-    }
-
-    /// A purl
-    pub async fn sbom_purl(&self) -> anyhow::Result<String> {
-        self.find_row(
-            r#"
-select
-    b.purl as result
-from
-    sbom_package_purl_ref a
-    left join qualified_purl b on a.qualified_purl_id = b.id
-limit 1
-"#,
-        )
-        .await
-        .and_then(|row| {
-            let value: Value = row.try_get("result")?;
-            let purl: CanonicalPurl = serde_json::from_value(value)?;
-            Ok::<String, anyhow::Error>(purl.to_string())
-        })
-        .context("function sbom_purl: no SBOM packages found in database")
-    }
-
-    /// A purl with vulnerabilities
-    pub async fn analysis_purl(&self) -> anyhow::Result<String> {
-        self.find_row(
-            r#"
-select distinct
-    d.vulnerability_id,
-    d.advisory_id,
-    a.purl as result
-from
-    qualified_purl a
-    left join versioned_purl b on a.versioned_purl_id = b.id
-    left join base_purl c on b.base_purl_id = c.id
-    inner join purl_status d on d.base_purl_id = c.id
-    inner join status e on e.id = d.status_id
-    inner join version_range f on d.version_range_id = f.id
-where
-    e.slug = 'affected'
-and
-    version_matches(b.version, f.*) = TRUE
-order by
-    vulnerability_id
-limit 1
-"#,
-        )
-        .await
-        .and_then(|row| {
-            let value: Value = row.try_get("result")?;
-            let purl: CanonicalPurl = serde_json::from_value(value)?;
-            Ok::<String, anyhow::Error>(purl.to_string())
-        })
-        .context("function analysis_purl: no affected PURLs found in database")
-    }
-
-    /// A purl ID for details lookup
-    pub async fn purl_details(&self) -> anyhow::Result<String> {
-        self.find(
-            r#"
-SELECT
-    spr.qualified_purl_id::text AS result,
-    COUNT(DISTINCT spl.license_id) AS license_count
-FROM
-    sbom_package_purl_ref spr
-JOIN
-    sbom_package sp ON spr.sbom_id = sp.sbom_id AND spr.node_id = sp.node_id
-LEFT JOIN
-    sbom_package_license spl ON sp.sbom_id = spl.sbom_id AND sp.node_id = spl.node_id
-GROUP BY
-    spr.qualified_purl_id
-ORDER BY
-    license_count DESC
-LIMIT 1;
-"#,
-        )
-        .await
-        .context("function purl_details: no PURLs found in database")
-    }
-
-    // A purl whose version matches redhat-[0-9]+$ regex
-    pub async fn purl_with_recommendations(&self) -> anyhow::Result<DisplayVec<String>> {
-        self.find_rows(
-            r#"
-SELECT
-    purl AS result
-FROM
-    qualified_purl
-WHERE
-    purl->>'version' ~ 'redhat-[0-9]+$'
-LIMIT 128;
-"#,
-        )
-        .await
-        .and_then(|rows| {
-            let mut result = vec![];
-            for row in rows {
-                let value: Value = row.try_get("result")?;
-                let purl: CanonicalPurl = serde_json::from_value(value)?;
-                result.push(purl.to_string());
-            }
-            Ok(DisplayVec(result))
-        })
-    }
+        };
 
-    /// Get a pool of deletable SBOMs (up to 100)
-    /// These SBOMs are selected based on having the most packages
-    pub async fn deletable_sboms(&self) -> anyhow::Result<Vec<String>> {
-        let mut db = crate::db::connect(&self.db).await?;
-
-        let rows = sqlx::query(
-            r#"
-SELECT
-    a.sbom_id::text as id,
-    count(b.*) as package_count
-FROM
-    sbom a
-    JOIN sbom_package b ON a.sbom_id = b.sbom_id
-GROUP BY
-    a.sbom_id
-ORDER BY
-    package_count DESC,
-    a.sbom_id
-LIMIT 100
-"#,
-        )
-        .fetch_all(&mut db)
-        .await?;
-
-        Ok(rows
-            .into_iter()
-            .map(|row| row.get::<String, _>("id"))
-            .collect())
-    }
-
-    /// A advisory ID for download and query the advisory details
-    pub async fn download_advisory(&self) -> anyhow::Result<String> {
-        self.find(
-            r#"
-SELECT id::text as result
-FROM public.advisory order by modified desc limit 1;"#,
-        )
-        .await
-        .context("function download_advisory: no advisories found in database")
-    }
-
-    /// An SBOM group UUID
-    pub async fn sbom_group(&self) -> anyhow::Result<String> {
-        self.find("SELECT id::text as result FROM sbom_group LIMIT 1")
-            .await
-            .context("function sbom_group: no SBOM groups found in database")
-    }
-
-    /// A product UUID
-    pub async fn product(&self) -> anyhow::Result<String> {
-        self.find("SELECT id::text as result FROM product LIMIT 1")
-            .await
-            .context("function product: no products found in database")
-    }
+        Self {
+            get_sbom: large_sbom_digest.clone(),
+            get_sbom_advisories: large_sbom_digest.clone(),
+            get_sbom_related: large_sbom_id("uri:uuid:"),
+            get_sbom_packages: large_sbom_id("uri:uuid:"),
 
-    /// An organization UUID
-    pub async fn organization(&self) -> anyhow::Result<String> {
-        self.find("SELECT id::text as result FROM organization LIMIT 1")
-            .await
-            .context("function organization: no organizations found in database")
-    }
+            get_vulnerability: Pool::new(candidates.max_vuln),
 
-    /// A base PURL key (type:namespace/name or type:name)
-    pub async fn base_purl(&self) -> anyhow::Result<String> {
-        self.find(
-            r#"
-SELECT
-    CASE
-        WHEN namespace IS NOT NULL AND namespace != ''
-        THEN 'pkg:' || type || '/' || namespace || '/' || name
-        ELSE 'pkg:' || type || '/' || name
-    END as result
-FROM base_purl
-LIMIT 1"#,
-        )
-        .await
-        .context("function base_purl: no base PURLs found in database")
-    }
+            sbom_by_package: sbom_purl.clone(),
+            sbom_license_ids: large_sbom_id("urn:uuid:"),
+            analyze_purl: Pool::new(candidates.analyze_purl),
+            get_purl_details: Pool::new(candidates.purl_details),
+            get_recommendations: candidates.recommendations,
+            delete_sbom_pool: Some(
+                candidates
+                    .deletable_sboms
+                    .iter()
+                    .map(|sbom_id| format!("urn:uuid:{sbom_id}"))
+                    .collect(),
+            ),
+            download_advisory: advisory.clone(),
+            get_advisory: advisory,
 
-    /// An importer name
-    pub async fn importer(&self) -> anyhow::Result<String> {
-        self.find("SELECT name as result FROM importer LIMIT 1")
-            .await
-            .context("function importer: no importers found in database")
+            download_sbom: large_sbom_digest.clone(),
+            get_sbom_license_export: large_sbom_id("urn:uuid:"),
+            count_sbom_by_package: sbom_purl,
+            get_sbom_group: Pool::new(candidates.sbom_group),
+            get_product: Pool::new(candidates.product),
+            get_organization: Pool::new(candidates.organization),
+            get_base_purl: Pool::new(candidates.base_purl),
+            get_analysis_component: large_sbom_digest,
+            render_sbom_graph: large_sbom_id("urn:uuid:"),
+            search_analysis_component,
+            get_importer: Pool::new(candidates.importer),
+            get_weakness: Some(Pool::from("CWE-79".to_string())),
+            get_spdx_license: Some(Pool::from("MIT".to_string())),
+        }
     }
 }
 
@@ -540,10 +306,80 @@ mod test {
             .expect("Must be ok");
     }
 
+    #[test]
+    fn purls() {
+        let scenario = super::Scenario::from(Candidates {
+            sbom_purl: vec!["pkg:rpm/redhat/a@1".into()],
+            analyze_purl: vec!["pkg:rpm/redhat/b@1".into(), "pkg:rpm/redhat/a@1".into()],
+            recommendations: Some(DisplayVec(vec!["pkg:maven/c/d@1".into()])),
+            ..Default::default()
+        });
+        assert_eq!(
+            scenario.purls(),
+            [
+                "pkg:maven/c/d@1",
+                "pkg:rpm/redhat/a@1",
+                "pkg:rpm/redhat/b@1"
+            ]
+        );
+    }
+
+    #[test]
+    fn analysis_component_names() {
+        let mut scenario = super::Scenario::from(Candidates {
+            sbom_purl: vec![
+                "pkg:rpm/redhat/openssl@3.0.7".into(),
+                "pkg:rpm/redhat/curl@7.76.1".into(),
+                "not a purl".into(),
+            ],
+            analyze_purl: vec!["pkg:rpm/redhat/openssl@3.0.1".into()],
+            ..Default::default()
+        });
+        assert_eq!(
+            scenario
+                .search_analysis_component
+                .as_ref()
+                .map(Pool::values),
+            Some(&["openssl".to_string(), "curl".to_string()][..])
+        );
+
+        // older scenario files, without the entry
+        scenario.search_analysis_component = None;
+        assert_eq!(
+            scenario
+                .analysis_component_names()
+                .as_ref()
+                .map(Pool::values),
+            Some(&["curl".to_string(), "openssl".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn pool_json5() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Scenario {
+            #[serde(with = "required")]
+            pub large_sbom: Option<Pool>,
+        }
+
+        let s = serde_json5::from_str::<Scenario>(r#"{"large_sbom": ["foo", "bar"]}"#)
+            .expect("Must be ok");
+        assert_eq!(
+            s.large_sbom.as_ref().map(Pool::values),
+            Some(&["foo".to_string(), "bar".to_string()][..])
+        );
+
+        let s = serde_json5::from_str::<Scenario>(r#"{"large_sbom": "foo"}"#).expect("Must be ok");
+        assert_eq!(
+            s.large_sbom.as_ref().map(Pool::values),
+            Some(&["foo".to_string()][..])
+        );
+    }
+
     #[test]
     fn full_scenario_roundtrip() {
         let json = r#"{
-            "get_sbom": "sha256:abc",
+            "get_sbom": ["sha256:abc", "sha256:def"],
             "get_sbom_advisories": "sha256:abc",
             "get_sbom_packages": "urn:uuid:abc",
             "get_sbom_related": "urn:uuid:abc",
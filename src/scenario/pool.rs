@@ -0,0 +1,233 @@
+use goose::goose::GooseUser;
+use rand::prelude::*;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// A non-empty pool of values for a scenario entry.
+///
+/// Serialized as a plain string if it contains a single value, as a list of strings otherwise.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pool(Vec<String>);
+
+impl Pool {
+    /// Create a new pool, returns `None` if there are no values.
+    pub fn new(values: Vec<String>) -> Option<Self> {
+        (!values.is_empty()).then_some(Self(values))
+    }
+
+    pub fn values(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Create a selector, picking values using the provided strategy.
+    pub fn selector(&self, strategy: Strategy) -> Selector {
+        Selector {
+            values: self.0.clone().into(),
+            strategy,
+            next: Default::default(),
+        }
+    }
+}
+
+impl From<String> for Pool {
+    fn from(value: String) -> Self {
+        Self(vec![value])
+    }
+}
+
+impl Serialize for Pool {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0.as_slice() {
+            [value] => s.serialize_str(value),
+            values => values.serialize(s),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Pool {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct De;
+
+        impl<'de> Visitor<'de> for De {
+            type Value = Pool;
+
+            fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                formatter.write_str("a string or a non-empty list of strings")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(Pool(vec![v.to_string()]))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut values = vec![];
+                while let Some(value) = seq.next_element::<String>()? {
+                    values.push(value);
+                }
+                Pool::new(values).ok_or_else(|| A::Error::custom("pool must not be empty"))
+            }
+        }
+
+        d.deserialize_any(De)
+    }
+}
+
+/// The strategy of picking a value from a pool, for every iteration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Strategy {
+    /// Pick a random value
+    #[default]
+    Random,
+    /// Pick the values one after the other, shared by all users
+    RoundRobin,
+    /// Let each user always pick the same value
+    Sticky,
+}
+
+/// Picks values from a [`Pool`] for a transaction.
+#[derive(Clone, Debug)]
+pub struct Selector {
+    values: Arc<[String]>,
+    strategy: Strategy,
+    next: Arc<AtomicUsize>,
+}
+
+impl Selector {
+    fn index(&self, user_index: usize) -> usize {
+        match self.strategy {
+            Strategy::Random => rand::rng().random_range(0..self.values.len()),
+            Strategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.values.len(),
+            Strategy::Sticky => user_index % self.values.len(),
+        }
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &*self.values {
+            [value] => f.write_str(value),
+            values => write!(f, "pool:{}", values.len()),
+        }
+    }
+}
+
+/// An argument of a transaction, which gets evaluated for every iteration.
+pub trait Select {
+    type Output;
+
+    fn select(&self, user: &GooseUser) -> Self::Output;
+}
+
+impl Select for Selector {
+    type Output = String;
+
+    fn select(&self, user: &GooseUser) -> String {
+        self.values[self.index(user.weighted_users_index)].clone()
+    }
+}
+
+/// Plain values are passed on as they are.
+impl Select for u64 {
+    type Output = u64;
+
+    fn select(&self, _: &GooseUser) -> u64 {
+        *self
+    }
+}
+
+/// Shared values, like a configuration, are the same for every iteration.
+impl<T> Select for Arc<T> {
+    type Output = Arc<T>;
+
+    fn select(&self, _: &GooseUser) -> Arc<T> {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn selector(strategy: Strategy) -> Selector {
+        Pool::new(vec!["a".into(), "b".into(), "c".into()])
+            .expect("Must not be empty")
+            .selector(strategy)
+    }
+
+    #[test]
+    fn single_roundtrip() {
+        let pool: Pool = serde_json5::from_str(r#""foo""#).expect("Must be ok");
+        assert_eq!(pool.values(), ["foo"]);
+        assert_eq!(
+            serde_json::to_string(&pool).expect("Must be ok"),
+            r#""foo""#
+        );
+    }
+
+    #[test]
+    fn list_roundtrip() {
+        let pool: Pool = serde_json5::from_str(r#"["foo", "bar"]"#).expect("Must be ok");
+        assert_eq!(pool.values(), ["foo", "bar"]);
+        assert_eq!(
+            serde_json::to_string(&pool).expect("Must be ok"),
+            r#"["foo","bar"]"#
+        );
+    }
+
+    #[test]
+    fn empty_list() {
+        serde_json5::from_str::<Pool>("[]").expect_err("Must be an error");
+    }
+
+    #[test]
+    fn round_robin() {
+        let selector = selector(Strategy::RoundRobin);
+        let picked: Vec<_> = (0..4).map(|i| selector.index(i)).collect();
+        assert_eq!(picked, [0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn sticky() {
+        let selector = selector(Strategy::Sticky);
+        assert_eq!(selector.index(4), selector.index(4));
+        assert_eq!(selector.index(4), 1);
+    }
+
+    #[test]
+    fn random_in_range() {
+        let selector = selector(Strategy::Random);
+        assert!((0..100).all(|_| selector.index(0) < 3));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(selector(Strategy::Random).to_string(), "pool:3");
+        assert_eq!(
+            Pool::from("foo".to_string())
+                .selector(Strategy::Random)
+                .to_string(),
+            "foo"
+        );
+    }
+}
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CanonicalPurl {
@@ -31,6 +32,25 @@ impl Display for CanonicalPurl {
     }
 }
 
+impl FromStr for CanonicalPurl {
+    type Err = packageurl::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let purl = PackageUrl::from_str(s)?;
+        Ok(Self {
+            ty: purl.ty().to_string(),
+            namespace: purl.namespace().map(ToString::to_string),
+            name: purl.name().to_string(),
+            version: purl.version().map(ToString::to_string),
+            qualifiers: purl
+                .qualifiers()
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,4 +149,19 @@ mod tests {
         let back: CanonicalPurl = serde_json::from_str(&json).unwrap();
         assert_eq!(purl, back);
     }
+
+    #[test]
+    fn parse() {
+        let purl: CanonicalPurl = "pkg:rpm/redhat/openssl@3.0.0?arch=x86_64"
+            .parse()
+            .expect("must parse");
+        assert_eq!(purl.ty, "rpm");
+        assert_eq!(purl.namespace.as_deref(), Some("redhat"));
+        assert_eq!(purl.name, "openssl");
+        assert_eq!(purl.version.as_deref(), Some("3.0.0"));
+        assert_eq!(
+            purl.qualifiers.get("arch").map(String::as_str),
+            Some("x86_64")
+        );
+    }
 }
@@ -0,0 +1,393 @@
+use crate::{oidc::OpenIdTokenProvider, scenario::discover::has_findings, utils::truncate_middle};
+use reqwest_12::{Client, RequestBuilder, StatusCode};
+use serde_json::{Map, Value, json};
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+use urlencoding::encode;
+
+const MAX_VALUE_DISPLAY: usize = 48;
+
+/// The outcome of checking a single scenario value against the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    /// Found, but took longer than the threshold
+    Slow,
+    /// The server doesn't know about the value
+    Missing,
+    /// The check failed for another reason
+    Error,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ok => "OK",
+            Self::Slow => "slow",
+            Self::Missing => "missing",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// How to tell if a value is known to the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Expect {
+    /// Any successful response
+    Found,
+    /// A successful response with a non-empty list of items
+    Items,
+    /// A successful vulnerability analysis with findings
+    Findings,
+}
+
+/// The result of checking a single value of a scenario entry
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub field: String,
+    pub value: String,
+    pub outcome: Outcome,
+    pub status: Option<StatusCode>,
+    pub duration: Duration,
+}
+
+/// Checks the values of a scenario against a trustify instance.
+pub struct Validator {
+    client: Client,
+    host: String,
+    provider: Option<OpenIdTokenProvider>,
+    slow: Duration,
+}
+
+impl Validator {
+    pub fn new(host: String, provider: Option<OpenIdTokenProvider>, slow: Duration) -> Self {
+        Self {
+            client: Client::new(),
+            host,
+            provider,
+            slow,
+        }
+    }
+
+    /// Check all values of all non-null entries of a scenario, serialized as a JSON object.
+    pub async fn validate(&self, scenario: &Map<String, Value>) -> Vec<Check> {
+        let mut result = vec![];
+
+        for (field, value) in scenario {
+            for value in values(value) {
+                let Some((request, expect)) = self.request(field, value) else {
+                    continue;
+                };
+                let request = match &self.provider {
+                    Some(provider) => provider.authorize(request).await,
+                    None => Ok(request),
+                };
+                let check = match request {
+                    Ok(request) => self.check(field, value, request, expect).await,
+                    Err(err) => {
+                        log::warn!("Failed to authorize request: {err:#}");
+                        Check {
+                            field: field.clone(),
+                            value: value.to_string(),
+                            outcome: Outcome::Error,
+                            status: None,
+                            duration: Duration::ZERO,
+                        }
+                    }
+                };
+                result.push(check);
+            }
+        }
+
+        result
+    }
+
+    async fn check(
+        &self,
+        field: &str,
+        value: &str,
+        request: RequestBuilder,
+        expect: Expect,
+    ) -> Check {
+        let start = Instant::now();
+        let (outcome, status) = match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                let outcome = match status {
+                    StatusCode::NOT_FOUND => Outcome::Missing,
+                    status if !status.is_success() => Outcome::Error,
+                    _ if expect == Expect::Found => Outcome::Ok,
+                    _ => match response.json::<Value>().await {
+                        Ok(body) if found(expect, value, &body) => Outcome::Ok,
+                        Ok(_) => Outcome::Missing,
+                        Err(_) => Outcome::Error,
+                    },
+                };
+                (outcome, Some(status))
+            }
+            Err(err) => {
+                log::warn!("Failed to check {field} [{value}]: {err}");
+                (Outcome::Error, None)
+            }
+        };
+        let duration = start.elapsed();
+
+        let outcome = match outcome {
+            Outcome::Ok if duration >= self.slow => Outcome::Slow,
+            outcome => outcome,
+        };
+
+        Check {
+            field: field.to_string(),
+            value: value.to_string(),
+            outcome,
+            status,
+            duration,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.host.trim_end_matches('/'))
+    }
+
+    /// The request issued for a value of an entry, following the transaction using it
+    fn request(&self, field: &str, value: &str) -> Option<(RequestBuilder, Expect)> {
+        let get = |path: String| self.client.get(self.url(&path));
+        let post = |path: &str, body: Value| self.client.post(self.url(path)).json(&body);
+        let urn = |id: &str| encode(&format!("urn:uuid:{id}")).into_owned();
+
+        Some(match field {
+            "get_sbom" | "delete_sbom_pool" => {
+                (get(format!("/api/v3/sbom/{value}")), Expect::Found)
+            }
+            "get_sbom_advisories" => (get(format!("/api/v3/sbom/{value}/advisory")), Expect::Found),
+            "get_sbom_packages" => (get(format!("/api/v3/sbom/{value}/packages")), Expect::Found),
+            "get_sbom_related" => (get(format!("/api/v3/sbom/{value}/related")), Expect::Found),
+            "get_vulnerability" => (get(format!("/api/v3/vulnerability/{value}")), Expect::Found),
+            "sbom_by_package" | "count_sbom_by_package" => (
+                get(format!("/api/v3/sbom/by-package?purl={}", encode(value))),
+                Expect::Items,
+            ),
+            "sbom_license_ids" => (
+                get(format!("/api/v3/sbom/{}/all-license-ids", encode(value))),
+                Expect::Found,
+            ),
+            "analyze_purl" => (
+                post("/api/v3/vulnerability/analyze", json!({ "purls": [value] })),
+                Expect::Findings,
+            ),
+            "get_purl_details" => (get(format!("/api/v3/purl/{value}")), Expect::Found),
+            "get_recommendations" => (
+                post("/api/v3/purl/recommend", json!({ "purls": [value] })),
+                Expect::Found,
+            ),
+            "download_advisory" => (
+                get(format!("/api/v3/advisory/{}/download", urn(value))),
+                Expect::Found,
+            ),
+            "get_advisory" => (
+                get(format!("/api/v3/advisory/{}", urn(value))),
+                Expect::Found,
+            ),
+            "download_sbom" => (
+                get(format!("/api/v3/sbom/{}/download", encode(value))),
+                Expect::Found,
+            ),
+            "get_sbom_license_export" => (
+                get(format!("/api/v3/sbom/{}/license-export", encode(value))),
+                Expect::Found,
+            ),
+            "get_sbom_group" => (get(format!("/api/v3/group/sbom/{value}")), Expect::Found),
+            "get_product" => (get(format!("/api/v3/product/{value}")), Expect::Found),
+            "get_organization" => (get(format!("/api/v3/organization/{value}")), Expect::Found),
+            "get_base_purl" => (
+                get(format!("/api/v3/purl/base/{}", encode(value))),
+                Expect::Found,
+            ),
+            "get_analysis_component" => (
+                get(format!("/api/v3/analysis/component/{}", encode(value))),
+                Expect::Items,
+            ),
+            "search_analysis_component" => (
+                get(format!("/api/v3/analysis/component?q={}", encode(value))),
+                Expect::Items,
+            ),
+            "render_sbom_graph" => (
+                get(format!(
+                    "/api/v3/analysis/sbom/{}/render.dot",
+                    encode(value)
+                )),
+                Expect::Found,
+            ),
+            "get_importer" => (get(format!("/api/v3/importer/{value}")), Expect::Found),
+            "get_weakness" => (get(format!("/api/v3/weakness/{value}")), Expect::Found),
+            "get_spdx_license" => (
+                get(format!("/api/v3/license/spdx/license/{value}")),
+                Expect::Found,
+            ),
+            _ => return None,
+        })
+    }
+}
+
+/// The values of a scenario entry, which is either a single string or a list of strings
+fn values(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(value) => vec![value],
+        Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    }
+}
+
+/// Check if a successful response body contains the value
+fn found(expect: Expect, value: &str, body: &Value) -> bool {
+    match expect {
+        Expect::Found => true,
+        Expect::Items => match body {
+            Value::Array(items) => !items.is_empty(),
+            body => body["items"]
+                .as_array()
+                .is_some_and(|items| !items.is_empty()),
+        },
+        Expect::Findings => body.get(value).is_some_and(has_findings),
+    }
+}
+
+/// Remove missing values from the scenario, setting entries to `null` if no value remains.
+///
+/// Returns the number of removed values.
+pub fn remove_missing(scenario: &mut Map<String, Value>, checks: &[Check]) -> usize {
+    let missing = |field: &str, value: &str| {
+        checks
+            .iter()
+            .any(|c| c.outcome == Outcome::Missing && c.field == field && c.value == value)
+    };
+
+    let mut removed = 0;
+    for (field, entry) in scenario.iter_mut() {
+        match entry {
+            Value::String(value) if missing(field, value) => {
+                *entry = Value::Null;
+                removed += 1;
+            }
+            Value::Array(values) => {
+                let len = values.len();
+                values.retain(|value| !value.as_str().is_some_and(|value| missing(field, value)));
+                removed += len - values.len();
+                if values.is_empty() {
+                    *entry = Value::Null;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    removed
+}
+
+/// Print the checks as a table
+pub fn print_table(checks: &[Check]) {
+    let field_width = checks
+        .iter()
+        .map(|c| c.field.len())
+        .max()
+        .unwrap_or_default()
+        .max("ENTRY".len());
+
+    println!(
+        "{:<field_width$}  {:<MAX_VALUE_DISPLAY$}  {:<7}  {:>6}  {:>10}",
+        "ENTRY", "VALUE", "RESULT", "STATUS", "TIME"
+    );
+    for check in checks {
+        println!(
+            "{:<field_width$}  {:<MAX_VALUE_DISPLAY$}  {:<7}  {:>6}  {:>10}",
+            check.field,
+            truncate_middle(&check.value, MAX_VALUE_DISPLAY),
+            check.outcome,
+            check
+                .status
+                .map(|status| status.as_u16().to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            format!("{}ms", check.duration.as_millis()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(field: &str, value: &str, outcome: Outcome) -> Check {
+        Check {
+            field: field.into(),
+            value: value.into(),
+            outcome,
+            status: None,
+            duration: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn entry_values() {
+        assert_eq!(values(&json!("a")), ["a"]);
+        assert_eq!(values(&json!(["a", "b"])), ["a", "b"]);
+        assert!(values(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn found_items() {
+        assert!(found(Expect::Items, "", &json!({"items": [1], "total": 1})));
+        assert!(!found(Expect::Items, "", &json!({"items": [], "total": 0})));
+        assert!(found(Expect::Items, "", &json!([1])));
+    }
+
+    #[test]
+    fn found_findings() {
+        assert!(found(
+            Expect::Findings,
+            "pkg:a",
+            &json!({"pkg:a": {"details": [{"id": "CVE-1"}]}})
+        ));
+        assert!(!found(
+            Expect::Findings,
+            "pkg:a",
+            &json!({"pkg:a": {"details": []}})
+        ));
+        assert!(!found(Expect::Findings, "pkg:a", &json!({})));
+    }
+
+    #[test]
+    fn remove() {
+        let Value::Object(mut scenario) = json!({
+            "get_sbom": "sha256:a",
+            "get_advisory": "b",
+            "get_product": ["c", "d"],
+            "get_importer": ["e"],
+            "get_weakness": null,
+        }) else {
+            panic!("must be an object");
+        };
+
+        let checks = [
+            check("get_sbom", "sha256:a", Outcome::Missing),
+            check("get_advisory", "b", Outcome::Slow),
+            check("get_product", "c", Outcome::Missing),
+            check("get_product", "d", Outcome::Ok),
+            check("get_importer", "e", Outcome::Missing),
+        ];
+
+        assert_eq!(remove_missing(&mut scenario, &checks), 3);
+        assert_eq!(
+            Value::Object(scenario),
+            json!({
+                "get_sbom": null,
+                "get_advisory": "b",
+                "get_product": ["d"],
+                "get_importer": null,
+                "get_weakness": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Sampling the Prometheus metrics of trustify itself during a run, like its memory and database pool usage.
+
+use crate::{cli::ServerMetrics, thresholds::matches};
+use anyhow::Context;
+use reqwest_12::Client;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{sync::Mutex, task::JoinHandle};
+
+/// A sample of a series
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Sample {
+    /// Seconds since the sampler started
+    pub elapsed: u64,
+    pub value: f64,
+}
+
+/// The samples of a single series, like `db_pool_connections{state="idle"}`
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Series {
+    /// The name of the metric, with its labels
+    pub name: String,
+    pub samples: Vec<Sample>,
+}
+
+/// Scrapes the metrics endpoint of the server in the background, for the duration of a run.
+pub(crate) struct Sampler {
+    series: Arc<Mutex<BTreeMap<String, Vec<Sample>>>>,
+    task: JoinHandle<()>,
+}
+
+impl Sampler {
+    pub fn start(config: &ServerMetrics, url: String) -> Self {
+        let series: Arc<Mutex<BTreeMap<String, Vec<Sample>>>> = Arc::default();
+        let interval: Duration = config.server_metrics_interval.into();
+        let patterns = config.server_metrics_series.clone();
+
+        let task = tokio::spawn({
+            let series = series.clone();
+            async move {
+                let client = Client::new();
+                let start = Instant::now();
+                let mut ticks = tokio::time::interval(interval);
+                loop {
+                    ticks.tick().await;
+                    let text = match scrape(&client, &url, interval).await {
+                        Ok(text) => text,
+                        Err(err) => {
+                            log::warn!("Unable to scrape the server metrics: {err:#}");
+                            continue;
+                        }
+                    };
+
+                    let elapsed = start.elapsed().as_secs();
+                    let mut series = series.lock().await;
+                    for (name, value) in select(&text, &patterns) {
+                        series
+                            .entry(name.to_string())
+                            .or_default()
+                            .push(Sample { elapsed, value });
+                    }
+                }
+            }
+        });
+
+        Self { series, task }
+    }
+
+    /// Stop sampling, returning the series sampled so far.
+    pub async fn stop(self) -> Vec<Series> {
+        self.task.abort();
+        let _ = self.task.await;
+        std::mem::take(&mut *self.series.lock().await)
+            .into_iter()
+            .map(|(name, samples)| Series { name, samples })
+            .collect()
+    }
+}
+
+async fn scrape(client: &Client, url: &str, timeout: Duration) -> anyhow::Result<String> {
+    Ok(client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?)
+}
+
+/// The series of the Prometheus text format, which have a name matching one of the patterns.
+///
+/// Histogram buckets are skipped, they are too many to chart.
+fn select<'a>(text: &'a str, patterns: &'a [String]) -> impl Iterator<Item = (&'a str, f64)> + 'a {
+    text.lines().filter_map(parse).filter(|(series, _)| {
+        let name = series.split('{').next().unwrap_or_default();
+        !name.ends_with("_bucket") && patterns.iter().any(|pattern| matches(pattern, name))
+    })
+}
+
+/// Parse a sample line, into the series and its value, ignoring an optional timestamp.
+///
+/// Values which aren't finite, like `NaN` or `+Inf`, are skipped, JSON can't represent them.
+fn parse(line: &str) -> Option<(&str, f64)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (series, rest) = match line.rfind('}') {
+        // label values may contain spaces
+        Some(close) => line.split_at(close + 1),
+        None => line.split_once(char::is_whitespace)?,
+    };
+    let value = rest
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite())?;
+
+    Some((series.trim(), value))
+}
+
+/// Print the range of every series
+pub(crate) fn print_table(series: &[Series]) {
+    if series.is_empty() {
+        println!("No server metrics sampled");
+        return;
+    }
+
+    let width = series
+        .iter()
+        .map(|s| s.name.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("SERIES".len());
+
+    println!(
+        "{:<width$}  {:>14}  {:>14}  {:>14}",
+        "SERIES", "MIN", "MAX", "LAST"
+    );
+    for s in series {
+        let (min, max) = range(&s.samples);
+        println!(
+            "{:<width$}  {min:>14}  {max:>14}  {:>14}",
+            s.name,
+            s.samples
+                .last()
+                .map(|sample| sample.value)
+                .unwrap_or_default(),
+        );
+    }
+}
+
+/// The smallest and the largest value of the samples
+fn range(samples: &[Sample]) -> (f64, f64) {
+    samples
+        .iter()
+        .map(|sample| sample.value)
+        .fold(None, |range, value| match range {
+            None => Some((value, value)),
+            Some((min, max)) => Some((f64::min(min, value), f64::max(max, value))),
+        })
+        .unwrap_or_default()
+}
+
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 200.0;
+/// The space left of the chart, for the labels of the values
+const CHART_MARGIN: f64 = 100.0;
+
+/// A section of an HTML report, charting the server metrics.
+pub(crate) fn html(series: &[Series]) -> String {
+    chart(
+        "Server Metrics",
+        "Sampled from the metrics endpoint of the server during the run.",
+        series,
+    )
+}
+
+/// A section of an HTML report, charting every series.
+pub(crate) fn chart(title: &str, description: &str, series: &[Series]) -> String {
+    let mut result = format!(
+        "<div class=\"chart\">\n<h2>{}</h2>\n<p>{}</p>\n",
+        escape(title),
+        escape(description)
+    );
+
+    for s in series {
+        let (min, max) = range(&s.samples);
+        let duration = s
+            .samples
+            .last()
+            .map(|sample| sample.elapsed)
+            .unwrap_or_default();
+
+        let points: Vec<_> = s
+            .samples
+            .iter()
+            .map(|sample| {
+                let x = match duration {
+                    0 => 0.0,
+                    duration => sample.elapsed as f64 / duration as f64,
+                };
+                // constant series are drawn in the middle
+                let y = if max > min {
+                    (sample.value - min) / (max - min)
+                } else {
+                    0.5
+                };
+                format!(
+                    "{:.1},{:.1}",
+                    CHART_MARGIN + x * (CHART_WIDTH - CHART_MARGIN),
+                    (1.0 - y) * (CHART_HEIGHT - 20.0) + 10.0
+                )
+            })
+            .collect();
+
+        let _ = write!(
+            result,
+            r##"<h3>{name}</h3>
+<svg width="{CHART_WIDTH}" height="{height}" viewBox="0 0 {CHART_WIDTH} {height}" font-family="sans-serif" font-size="12">
+<text x="0" y="14">{max}</text>
+<text x="0" y="{CHART_HEIGHT}">{min}</text>
+<line x1="{CHART_MARGIN}" y1="{CHART_HEIGHT}" x2="{CHART_WIDTH}" y2="{CHART_HEIGHT}" stroke="#999"/>
+<polyline points="{points}" fill="none" stroke="#2c6fbb" stroke-width="2"/>
+<text x="{CHART_MARGIN}" y="{label}">0s</text>
+<text x="{CHART_WIDTH}" y="{label}" text-anchor="end">{duration}s</text>
+</svg>
+"##,
+            name = escape(&s.name),
+            height = CHART_HEIGHT + 20.0,
+            label = CHART_HEIGHT + 16.0,
+            points = points.join(" "),
+        );
+    }
+
+    result.push_str("</div>\n");
+    result
+}
+
+/// Escape text for HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Add a section to the HTML reports of goose, other reports are left alone.
+pub(crate) fn embed(report_files: &[String], section: &str) -> anyhow::Result<()> {
+    for file in report_files {
+        let html = Path::new(file)
+            .extension()
+            .is_some_and(|extension| extension == "html" || extension == "htm");
+        if !html {
+            continue;
+        }
+
+        let report =
+            std::fs::read_to_string(file).with_context(|| format!("reading the report: {file}"))?;
+        std::fs::write(file, insert(&report, section))
+            .with_context(|| format!("writing the report: {file}"))?;
+    }
+
+    Ok(())
+}
+
+/// Insert a section at the end of the body of an HTML document
+fn insert(report: &str, section: &str) -> String {
+    match report.rfind("</body>") {
+        Some(index) => format!("{}{section}{}", &report[..index], &report[index..]),
+        None => format!("{report}{section}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::baseline::RunResult;
+
+    const TEXT: &str = r#"# HELP process_resident_memory_bytes Resident memory size in bytes.
+# TYPE process_resident_memory_bytes gauge
+process_resident_memory_bytes 1.2345e8
+# TYPE db_pool_connections gauge
+db_pool_connections{state="idle"} 3
+db_pool_connections{state="in use, waiting"} 7 1712345678000
+# TYPE db_pool_acquire_seconds histogram
+db_pool_acquire_seconds_bucket{le="0.1"} 12
+db_pool_acquire_seconds_count 12
+http_requests_in_flight{endpoint="/api/v3/sbom"} 4
+http_requests_in_flight{endpoint="/api/v3/purl"} +Inf
+db_pool_connections{state="closed"} NaN
+http_requests_total{status="200"} 1024
+"#;
+
+    #[test]
+    fn parse_lines() {
+        assert_eq!(parse("up 1"), Some(("up", 1.0)));
+        assert_eq!(
+            parse(r#"db_pool_connections{state="in use, waiting"} 7 1712345678000"#),
+            Some((r#"db_pool_connections{state="in use, waiting"}"#, 7.0))
+        );
+        assert_eq!(parse("# TYPE up gauge"), None);
+        assert_eq!(parse("up"), None);
+        assert_eq!(parse("up not-a-number"), None);
+        assert_eq!(parse("up NaN"), None);
+        assert_eq!(parse("up -Inf"), None);
+    }
+
+    #[test]
+    fn select_series() {
+        let patterns = vec![
+            "process_resident_memory_bytes".to_string(),
+            "*pool*".to_string(),
+            "*in_flight*".to_string(),
+        ];
+        let series: Vec<_> = select(TEXT, &patterns).collect();
+
+        assert_eq!(
+            series,
+            [
+                ("process_resident_memory_bytes", 1.2345e8),
+                (r#"db_pool_connections{state="idle"}"#, 3.0),
+                (r#"db_pool_connections{state="in use, waiting"}"#, 7.0),
+                ("db_pool_acquire_seconds_count", 12.0),
+                (r#"http_requests_in_flight{endpoint="/api/v3/sbom"}"#, 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn store_and_load() {
+        let patterns = vec!["*".to_string()];
+        let mut result = RunResult::new(10, &[]);
+        result.server_metrics = select(TEXT, &patterns)
+            .map(|(name, value)| Series {
+                name: name.to_string(),
+                samples: vec![Sample { elapsed: 0, value }],
+            })
+            .collect();
+
+        let file = std::env::temp_dir().join(format!("result-{}.json", uuid::Uuid::new_v4()));
+        let file = file.to_string_lossy();
+        result.store(&file).expect("must store");
+        let loaded = RunResult::load(&file);
+        let _ = std::fs::remove_file(&*file);
+
+        assert_eq!(loaded.expect("must load"), result);
+    }
+
+    #[test]
+    fn chart() {
+        let series = [Series {
+            name: r#"db_pool_connections{state="idle"}"#.into(),
+            samples: vec![
+                Sample {
+                    elapsed: 0,
+                    value: 1.0,
+                },
+                Sample {
+                    elapsed: 10,
+                    value: 3.0,
+                },
+            ],
+        }];
+        let html = html(&series);
+
+        assert!(html.contains("<h2>Server Metrics</h2>"));
+        assert!(html.contains("<h3>db_pool_connections{state=&quot;idle&quot;}</h3>"));
+        assert!(html.contains(r#"<polyline points="100.0,190.0 800.0,10.0""#));
+        assert!(html.contains(">10s</text>"));
+
+        assert_eq!(
+            insert("<html><body><h1>Goose</h1></body></html>", "<div/>"),
+            "<html><body><h1>Goose</h1><div/></body></html>"
+        );
+    }
+}
@@ -0,0 +1,17 @@
+//! Observing the SQL statements trustify runs during a run, using `pg_stat_statements` of its database.
+
+#[cfg(feature = "postgres")]
+mod observer;
+
+#[cfg(feature = "postgres")]
+pub(crate) use observer::{Observer, StatementStats, print_tables};
+
+/// A statement run during the run, times in milliseconds.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Statement {
+    pub query: String,
+    pub calls: u64,
+    pub total_time: f64,
+    pub mean_time: f64,
+    pub rows: u64,
+}
@@ -0,0 +1,311 @@
+use super::Statement;
+use anyhow::Context;
+use sqlx::Row;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// How to separate the statements of the run from earlier ones
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum StatementStats {
+    /// Compare snapshots taken at the start and the end of the run, leaving the statistics alone
+    Snapshot,
+    /// Reset the statistics at the start of the run, which requires the privilege to do so
+    Reset,
+}
+
+/// The statistics of a statement, as kept by `pg_stat_statements`
+#[derive(Clone, PartialEq, Debug, Default)]
+struct Counters {
+    query: String,
+    calls: i64,
+    /// The total execution time, in milliseconds
+    total_time: f64,
+    rows: i64,
+}
+
+/// The counters of all statements, by query ID
+type Snapshot = HashMap<i64, Counters>;
+
+/// The statements of the database of trustify, summed up over all users
+const SNAPSHOT: &str = r#"
+SELECT
+    queryid,
+    min(query) AS query,
+    sum(calls)::bigint AS calls,
+    sum(total_exec_time) AS total_time,
+    sum(rows)::bigint AS rows
+FROM pg_stat_statements
+WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database())
+  AND queryid IS NOT NULL
+GROUP BY queryid
+"#;
+
+async fn snapshot(db: &mut sqlx::PgConnection) -> anyhow::Result<Snapshot> {
+    sqlx::query(SNAPSHOT)
+        .fetch_all(db)
+        .await
+        .context("reading pg_stat_statements, which requires the extension to be created")?
+        .iter()
+        .map(|row| {
+            Ok((
+                row.try_get("queryid")?,
+                Counters {
+                    query: row.try_get("query")?,
+                    calls: row.try_get("calls")?,
+                    total_time: row.try_get("total_time")?,
+                    rows: row.try_get("rows")?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Observes the statements of the database, from the start to the end of a run.
+pub(crate) struct Observer {
+    database_url: String,
+    /// The statistics at the start of the run
+    start: Snapshot,
+    /// The statistics at the start of the current window, like a stage of a load profile
+    window: Mutex<Snapshot>,
+}
+
+impl Observer {
+    pub async fn start(database_url: String, mode: StatementStats) -> anyhow::Result<Self> {
+        let mut db = crate::db::connect(&database_url).await?;
+        let start = match mode {
+            StatementStats::Snapshot => snapshot(&mut db).await?,
+            StatementStats::Reset => {
+                sqlx::query("SELECT pg_stat_statements_reset()")
+                    .execute(&mut db)
+                    .await
+                    .context("resetting pg_stat_statements")?;
+                Snapshot::default()
+            }
+        };
+
+        Ok(Self {
+            database_url,
+            window: Mutex::new(start.clone()),
+            start,
+        })
+    }
+
+    async fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        let mut db = crate::db::connect(&self.database_url).await?;
+        snapshot(&mut db).await
+    }
+
+    /// The `top` statements since the end of the previous window, starting the next one.
+    pub async fn window(&self, top: usize) -> anyhow::Result<Vec<Statement>> {
+        let now = self.snapshot().await?;
+        let mut window = self.window.lock().await;
+        let statements = select(diff(&window, now.clone()), top);
+        *window = now;
+        Ok(statements)
+    }
+
+    /// The `top` statements of the run, by total time, mean time and calls.
+    pub async fn stop(self, top: usize) -> anyhow::Result<Vec<Statement>> {
+        let now = self.snapshot().await?;
+        Ok(select(diff(&self.start, now), top))
+    }
+}
+
+/// The statements run between two snapshots
+fn diff(before: &Snapshot, after: Snapshot) -> Vec<Statement> {
+    after
+        .into_iter()
+        .filter_map(|(id, after)| {
+            let (calls, total_time, rows) = match before.get(&id) {
+                Some(before) if before.calls <= after.calls => (
+                    after.calls - before.calls,
+                    after.total_time - before.total_time,
+                    after.rows - before.rows,
+                ),
+                // new, or the statistics got reset or evicted in between
+                _ => (after.calls, after.total_time, after.rows),
+            };
+
+            let calls = u64::try_from(calls).ok().filter(|calls| *calls > 0)?;
+            Some(Statement {
+                query: after.query,
+                calls,
+                total_time,
+                mean_time: total_time / calls as f64,
+                rows: u64::try_from(rows).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// A ranking of the statements
+struct Ranking {
+    title: &'static str,
+    /// Orders the statements, the first being the top one
+    order: fn(&Statement, &Statement) -> std::cmp::Ordering,
+}
+
+const RANKINGS: [Ranking; 3] = [
+    Ranking {
+        title: "Top statements by total time",
+        order: |a, b| b.total_time.total_cmp(&a.total_time),
+    },
+    Ranking {
+        title: "Top statements by mean time",
+        order: |a, b| b.mean_time.total_cmp(&a.mean_time),
+    },
+    Ranking {
+        title: "Top statements by calls",
+        order: |a, b| b.calls.cmp(&a.calls),
+    },
+];
+
+/// The statements being in the `top` of any ranking, by total time
+fn select(mut statements: Vec<Statement>, top: usize) -> Vec<Statement> {
+    let mut selected = vec![false; statements.len()];
+    let mut indexes: Vec<_> = (0..statements.len()).collect();
+    for ranking in RANKINGS {
+        indexes.sort_by(|a, b| (ranking.order)(&statements[*a], &statements[*b]));
+        for index in indexes.iter().take(top) {
+            selected[*index] = true;
+        }
+    }
+
+    let mut selected = selected.into_iter();
+    statements.retain(|_| selected.next().unwrap_or_default());
+    statements.sort_by(RANKINGS[0].order);
+    statements
+}
+
+/// The maximum length of a query in the tables
+const QUERY_WIDTH: usize = 120;
+
+/// Collapse the whitespace of a query, and cut it off if it's too long
+fn shorten(query: &str) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    match query.char_indices().nth(QUERY_WIDTH) {
+        Some((index, _)) => format!("{}…", &query[..index]),
+        None => query,
+    }
+}
+
+/// Print the `top` statements of every ranking
+pub(crate) fn print_tables(statements: &[Statement], top: usize) {
+    if statements.is_empty() {
+        println!("No statements recorded");
+        return;
+    }
+
+    println!(
+        "All statements of the database, including the ones of other clients. Run a workload of a single transaction to tie them to it."
+    );
+
+    for ranking in RANKINGS {
+        let mut statements: Vec<_> = statements.iter().collect();
+        statements.sort_by(|a, b| (ranking.order)(a, b));
+
+        println!("\n{}", ranking.title);
+        println!(
+            "{:>10}  {:>12}  {:>10}  {:>10}  QUERY",
+            "CALLS", "TOTAL (ms)", "MEAN (ms)", "ROWS"
+        );
+        for statement in statements.into_iter().take(top) {
+            println!(
+                "{:>10}  {:>12.1}  {:>10.2}  {:>10}  {}",
+                statement.calls,
+                statement.total_time,
+                statement.mean_time,
+                statement.rows,
+                shorten(&statement.query)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn counters(query: &str, calls: i64, total_time: f64) -> Counters {
+        Counters {
+            query: query.into(),
+            calls,
+            total_time,
+            rows: calls,
+        }
+    }
+
+    fn statement(query: &str, calls: u64, total_time: f64) -> Statement {
+        Statement {
+            query: query.into(),
+            calls,
+            total_time,
+            mean_time: total_time / calls as f64,
+            rows: calls,
+        }
+    }
+
+    #[test]
+    fn statements_of_the_run() {
+        let before: Snapshot = [
+            (1, counters("SELECT sbom", 10, 100.0)),
+            (2, counters("SELECT purl", 5, 50.0)),
+            (3, counters("SELECT advisory", 50, 500.0)),
+        ]
+        .into();
+        let after: Snapshot = [
+            (1, counters("SELECT sbom", 30, 400.0)),
+            // not run during the run
+            (2, counters("SELECT purl", 5, 50.0)),
+            // reset in between
+            (3, counters("SELECT advisory", 4, 80.0)),
+            (4, counters("SELECT package", 2, 10.0)),
+        ]
+        .into();
+
+        let mut statements = diff(&before, after);
+        statements.sort_by(|a, b| a.query.cmp(&b.query));
+
+        assert_eq!(
+            statements,
+            [
+                statement("SELECT advisory", 4, 80.0),
+                statement("SELECT package", 2, 10.0),
+                statement("SELECT sbom", 20, 300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_statements() {
+        let statements = vec![
+            statement("often", 1000, 500.0),
+            statement("slow", 1, 400.0),
+            statement("heavy", 100, 1000.0),
+            statement("cheap", 10, 1.0),
+        ];
+
+        let queries = |top| {
+            select(statements.clone(), top)
+                .into_iter()
+                .map(|statement| statement.query)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(queries(1), ["heavy", "often", "slow"]);
+        assert_eq!(queries(3), ["heavy", "often", "slow", "cheap"]);
+        assert!(queries(0).is_empty());
+    }
+
+    #[test]
+    fn shorten_query() {
+        assert_eq!(
+            shorten("SELECT *\n    FROM sbom\n    WHERE id = $1"),
+            "SELECT * FROM sbom WHERE id = $1"
+        );
+        let query = format!("SELECT {}", "ü".repeat(200));
+        let short = shorten(&query);
+        assert_eq!(short.chars().count(), QUERY_WIDTH + 1);
+        assert!(short.ends_with("üü…"));
+    }
+}
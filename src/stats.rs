@@ -0,0 +1,140 @@
+use goose::metrics::GooseMetrics;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Aggregated metrics of a transaction, merged by name across all scenarios running it.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TransactionStats {
+    /// The name of the transaction, as assigned by `tx!` or `list_tx`
+    pub name: String,
+    /// Number of runs by run-time in milliseconds
+    pub times: BTreeMap<usize, usize>,
+    /// The total run-time of all runs in milliseconds
+    pub total_time: usize,
+    /// The total number of runs
+    pub count: usize,
+    /// The number of failed runs
+    pub failed: usize,
+}
+
+impl TransactionStats {
+    /// Collect the stats of all named transactions, ordered by name.
+    pub fn from_metrics(metrics: &GooseMetrics) -> Vec<Self> {
+        let mut result = BTreeMap::<&str, Self>::new();
+
+        for transaction in metrics.transactions.iter().flatten() {
+            if transaction.transaction_name.is_empty() {
+                continue;
+            }
+            let stats = result
+                .entry(&transaction.transaction_name)
+                .or_insert_with(|| Self {
+                    name: transaction.transaction_name.clone(),
+                    ..Default::default()
+                });
+            for (time, count) in &transaction.times {
+                *stats.times.entry(*time).or_default() += count;
+            }
+            stats.total_time += transaction.total_time;
+            stats.count += transaction.success_count + transaction.fail_count;
+            stats.failed += transaction.fail_count;
+        }
+
+        result.into_values().collect()
+    }
+
+    /// Record a single run
+    pub fn record(&mut self, time: Duration, failed: bool) {
+        let time = time.as_millis() as usize;
+        *self.times.entry(time).or_default() += 1;
+        self.total_time += time;
+        self.count += 1;
+        if failed {
+            self.failed += 1;
+        }
+    }
+
+    /// The run-time in milliseconds, which `percentile` (0.0 to 1.0) of all runs didn't exceed.
+    pub fn percentile(&self, percentile: f64) -> usize {
+        let total: usize = self.times.values().sum();
+        let target = ((total as f64) * percentile).ceil().max(1.0) as usize;
+
+        let mut seen = 0;
+        for (time, count) in &self.times {
+            seen += count;
+            if seen >= target {
+                return *time;
+            }
+        }
+
+        0
+    }
+
+    /// The average run-time in milliseconds
+    pub fn mean(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.total_time as f64 / count as f64,
+        }
+    }
+
+    /// The ratio of failed runs, 0.0 to 1.0
+    pub fn error_rate(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.failed as f64 / count as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stats(times: &[(usize, usize)], failed: usize) -> TransactionStats {
+        TransactionStats {
+            name: "test".into(),
+            times: times.iter().copied().collect(),
+            total_time: times.iter().map(|(time, count)| time * count).sum(),
+            count: times.iter().map(|(_, count)| count).sum(),
+            failed,
+        }
+    }
+
+    #[test]
+    fn percentile() {
+        let stats = stats(&[(10, 50), (20, 45), (100, 4), (1000, 1)], 0);
+        assert_eq!(stats.percentile(0.5), 10);
+        assert_eq!(stats.percentile(0.95), 20);
+        assert_eq!(stats.percentile(0.99), 100);
+        assert_eq!(stats.percentile(1.0), 1000);
+    }
+
+    #[test]
+    fn empty() {
+        let stats = stats(&[], 0);
+        assert_eq!(stats.percentile(0.5), 0);
+        assert_eq!(stats.error_rate(), 0.0);
+    }
+
+    #[test]
+    fn mean() {
+        assert_eq!(stats(&[(10, 3), (50, 1)], 0).mean(), 20.0);
+    }
+
+    #[test]
+    fn record() {
+        let mut stats = TransactionStats::default();
+        stats.record(Duration::from_millis(10), false);
+        stats.record(Duration::from_micros(10_400), true);
+        assert_eq!(stats, {
+            let mut expected = self::stats(&[(10, 2)], 1);
+            expected.name = String::new();
+            expected
+        });
+    }
+
+    #[test]
+    fn error_rate() {
+        assert_eq!(stats(&[(10, 8)], 2).error_rate(), 0.25);
+    }
+}
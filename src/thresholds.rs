@@ -0,0 +1,295 @@
+use crate::stats::TransactionStats;
+use anyhow::{Context, bail};
+use serde::{Deserialize, Deserializer};
+use std::{io::BufReader, time::Duration};
+
+/// Service level objectives, evaluated against the metrics of a run.
+#[derive(Clone, PartialEq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Thresholds {
+    /// Thresholds by transaction, the first one matching a transaction name applies
+    pub transactions: Vec<Threshold>,
+}
+
+/// Latency and error rate budgets of transactions.
+#[derive(Clone, PartialEq, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Threshold {
+    /// The transaction name, as assigned by `tx!` or `list_tx`. May contain `*` as a wildcard.
+    pub name: String,
+
+    #[serde(default, deserialize_with = "duration")]
+    pub p50: Option<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub p95: Option<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub p99: Option<Duration>,
+
+    /// The maximum ratio of failed transactions, 0.0 to 1.0
+    #[serde(default)]
+    pub error_rate: Option<f64>,
+}
+
+/// Parse a humantime duration, like `250ms` or `2s`.
+fn duration<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(d)?
+        .map(|value| humantime::parse_duration(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// The evaluation of a single budget of a transaction.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Evaluation {
+    pub transaction: String,
+    pub metric: &'static str,
+    pub limit: String,
+    pub actual: String,
+    pub passed: bool,
+}
+
+impl Thresholds {
+    pub fn load(file: &str) -> anyhow::Result<Self> {
+        let thresholds: Self = serde_json5::from_reader(BufReader::new(
+            std::fs::File::open(file)
+                .with_context(|| format!("opening thresholds file: {file}"))?,
+        ))
+        .context("reading thresholds file")?;
+        if thresholds.transactions.is_empty() {
+            bail!("the thresholds file {file} defines no thresholds");
+        }
+        Ok(thresholds)
+    }
+
+    /// Evaluate all transactions which ran and match a threshold.
+    ///
+    /// Thresholds not matching any transaction which ran fail, as they are most likely misspelled.
+    pub fn evaluate(&self, stats: &[TransactionStats]) -> Vec<Evaluation> {
+        let mut result = vec![];
+        let stats: Vec<_> = stats.iter().filter(|stats| stats.count > 0).collect();
+
+        for threshold in &self.transactions {
+            if !stats
+                .iter()
+                .any(|stats| matches(&threshold.name, &stats.name))
+            {
+                result.push(Evaluation {
+                    transaction: threshold.name.clone(),
+                    metric: "runs",
+                    limit: ">0".into(),
+                    actual: "0".into(),
+                    passed: false,
+                });
+            }
+        }
+
+        for stats in stats {
+            let Some(threshold) = self
+                .transactions
+                .iter()
+                .find(|threshold| matches(&threshold.name, &stats.name))
+            else {
+                continue;
+            };
+
+            for (metric, limit, percentile) in [
+                ("p50", threshold.p50, 0.5),
+                ("p95", threshold.p95, 0.95),
+                ("p99", threshold.p99, 0.99),
+            ] {
+                if let Some(limit) = limit {
+                    let actual = stats.percentile(percentile);
+                    result.push(Evaluation {
+                        transaction: stats.name.clone(),
+                        metric,
+                        limit: format!("{}ms", limit.as_millis()),
+                        actual: format!("{actual}ms"),
+                        passed: actual as u128 <= limit.as_millis(),
+                    });
+                }
+            }
+
+            if let Some(limit) = threshold.error_rate {
+                let actual = stats.error_rate();
+                result.push(Evaluation {
+                    transaction: stats.name.clone(),
+                    metric: "error rate",
+                    limit: format!("{:.2}%", limit * 100.0),
+                    actual: format!("{:.2}%", actual * 100.0),
+                    passed: actual <= limit,
+                });
+            }
+        }
+
+        result
+    }
+}
+
+/// Match a name against a pattern, in which `*` matches any number of characters.
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(rest) = parts.next().and_then(|first| name.strip_prefix(first)) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard
+        return rest.is_empty();
+    };
+
+    let mut rest = rest;
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// Print the evaluations as a table
+pub(crate) fn print_table(evaluations: &[Evaluation]) {
+    let width = evaluations
+        .iter()
+        .map(|e| e.transaction.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("TRANSACTION".len());
+
+    println!(
+        "{:<width$}  {:<10}  {:>10}  {:>10}  RESULT",
+        "TRANSACTION", "METRIC", "LIMIT", "ACTUAL"
+    );
+    for evaluation in evaluations {
+        println!(
+            "{:<width$}  {:<10}  {:>10}  {:>10}  {}",
+            evaluation.transaction,
+            evaluation.metric,
+            evaluation.limit,
+            evaluation.actual,
+            match evaluation.passed {
+                true => "pass",
+                false => "FAIL",
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stats(name: &str, times: &[(usize, usize)], failed: usize) -> TransactionStats {
+        TransactionStats {
+            name: name.into(),
+            times: times.iter().copied().collect(),
+            total_time: times.iter().map(|(time, count)| time * count).sum(),
+            count: times.iter().map(|(_, count)| count).sum(),
+            failed,
+        }
+    }
+
+    #[test]
+    fn wildcard() {
+        assert!(matches("get_sbom", "get_sbom"));
+        assert!(!matches("get_sbom", "get_sbom[pool:3]"));
+        assert!(matches("get_sbom*", "get_sbom[pool:3]"));
+        assert!(matches("*", "/api/v3/sbom"));
+        assert!(matches(
+            "/api/v3/sbom?*",
+            "/api/v3/sbom?offset=100&limit=10"
+        ));
+        assert!(matches("get_*[*]", "get_advisory[abc]"));
+        assert!(!matches("get_*[*]", "get_advisory"));
+        assert!(!matches("*a*a", "a"));
+    }
+
+    #[test]
+    fn load() {
+        let thresholds: Thresholds = serde_json5::from_str(
+            r#"{
+  transactions: [
+    { name: "get_sbom*", p95: "500ms", error_rate: 0.01 },
+    { name: "*", p99: "5s" },
+  ],
+}"#,
+        )
+        .expect("must parse");
+        assert_eq!(
+            thresholds.transactions[0].p95,
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(thresholds.transactions[0].p50, None);
+        assert_eq!(thresholds.transactions[1].p99, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn invalid_duration() {
+        serde_json5::from_str::<Thresholds>(r#"{ transactions: [{ name: "*", p95: "fast" }] }"#)
+            .expect_err("must fail");
+    }
+
+    #[test]
+    fn evaluate() {
+        let thresholds: Thresholds = serde_json5::from_str(
+            r#"{
+  transactions: [
+    { name: "get_sbom*", p50: "20ms", p95: "50ms", error_rate: 0.1 },
+    { name: "*", p99: "1s" },
+  ],
+}"#,
+        )
+        .expect("must parse");
+
+        let evaluations = thresholds.evaluate(&[
+            stats("get_sbom[a]", &[(10, 90), (100, 10)], 20),
+            stats("/api/v3/sbom", &[(10, 10)], 0),
+            stats("not_run", &[], 0),
+        ]);
+
+        let result: Vec<_> = evaluations
+            .iter()
+            .map(|e| (e.transaction.as_str(), e.metric, e.passed))
+            .collect();
+        assert_eq!(
+            result,
+            [
+                ("get_sbom[a]", "p50", true),
+                ("get_sbom[a]", "p95", false),
+                ("get_sbom[a]", "error rate", false),
+                ("/api/v3/sbom", "p99", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched() {
+        let thresholds: Thresholds = serde_json5::from_str(
+            r#"{
+  transactions: [
+    { name: "get_sbmo*", p95: "50ms" },
+    { name: "not_run", error_rate: 0.1 },
+  ],
+}"#,
+        )
+        .expect("must parse");
+
+        let evaluations = thresholds.evaluate(&[
+            stats("get_sbom[a]", &[(10, 10)], 0),
+            stats("not_run", &[], 0),
+        ]);
+
+        let result: Vec<_> = evaluations
+            .iter()
+            .map(|e| (e.transaction.as_str(), e.metric, e.passed))
+            .collect();
+        assert_eq!(
+            result,
+            [("get_sbmo*", "runs", false), ("not_run", "runs", false)]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Trace context of the requests, correlating them with the traces of trustify.
+//!
+//! Every run of a transaction starts a new W3C trace, and every request gets a span of it, sent using the `traceparent`
+//! header. The names of the scenario and the transaction are sent as `X-Loadtest-Scenario` and
+//! `X-Loadtest-Transaction` headers.
+
+use crate::utils::{GooseUserData, wrap_transactions};
+use goose::goose::{Scenario, Transaction};
+use rand::prelude::*;
+use std::{fmt::Write, sync::Arc, time::Duration};
+
+/// The trace of a run of a transaction, kept in the session data of the user running it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceContext {
+    pub scenario: Arc<str>,
+    pub transaction: Arc<str>,
+    /// The trace ID, as 32 hex digits
+    pub trace_id: String,
+    /// Requests taking longer are logged as slow
+    pub slow: Option<Duration>,
+}
+
+impl TraceContext {
+    /// The `traceparent` header of a new span of the trace
+    pub fn traceparent(&self) -> String {
+        let span_id: u64 = rand::rng().random_range(1..=u64::MAX);
+        format!("00-{}-{span_id:016x}-01", self.trace_id)
+    }
+}
+
+/// A new random trace ID, which must not be all zeros
+fn trace_id() -> String {
+    let id: u128 = rand::rng().random_range(1..=u128::MAX);
+    format!("{id:032x}")
+}
+
+/// Encode a value for a header, which only allows visible ASCII characters.
+///
+/// Other characters, like the `…` of truncated IDs, are percent-encoded.
+pub fn header_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        if (c.is_ascii_graphic() || c == ' ') && c != '%' {
+            result.push(c);
+        } else {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                let _ = write!(result, "%{byte:02X}");
+            }
+        }
+    }
+    result
+}
+
+/// Start a new trace for every run of the transactions of a scenario.
+pub(crate) fn trace(scenario: Scenario, slow: Option<Duration>) -> Scenario {
+    let name: Arc<str> = scenario.name.as_str().into();
+    wrap_transactions(scenario, |transaction| {
+        traced(transaction, name.clone(), slow)
+    })
+}
+
+fn traced(mut transaction: Transaction, scenario: Arc<str>, slow: Option<Duration>) -> Transaction {
+    let function = transaction.function.clone();
+    let name: Arc<str> = transaction.name.as_str().into();
+
+    transaction.function = Arc::new(move |user| {
+        let function = function.clone();
+        GooseUserData::of(user).trace = Some(TraceContext {
+            scenario: scenario.clone(),
+            transaction: name.clone(),
+            trace_id: trace_id(),
+            slow,
+        });
+        Box::pin(async move {
+            let result = function(user).await;
+            GooseUserData::of(user).trace = None;
+            result
+        })
+    });
+
+    transaction
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn traceparent() {
+        let context = TraceContext {
+            scenario: "RestAPIUser".into(),
+            transaction: "get_sbom".into(),
+            trace_id: trace_id(),
+            slow: None,
+        };
+        let traceparent = context.traceparent();
+        let parts: Vec<_> = traceparent.split('-').collect();
+
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "00");
+        assert_eq!(parts[1], context.trace_id);
+        assert_eq!(parts[1].len(), 32);
+        assert_eq!(parts[2].len(), 16);
+        assert_eq!(parts[3], "01");
+        assert!(
+            traceparent[3..]
+                .chars()
+                .all(|c| c == '-' || c.is_ascii_hexdigit())
+        );
+        // every request gets its own span
+        assert_ne!(context.traceparent(), traceparent);
+    }
+
+    #[test]
+    fn header() {
+        assert_eq!(header_value("/api/v3/purl?q=curl"), "/api/v3/purl?q=curl");
+        assert_eq!(
+            header_value("get_sbom[sha256:012…5678]"),
+            "get_sbom[sha256:012%E2%80%A65678]"
+        );
+        assert_eq!(header_value("100%\n"), "100%25%0A");
+    }
+}
@@ -1,4 +1,6 @@
-use std::fmt::Display;
+use crate::{live::LiveMetrics, oidc::OpenIdTokenProvider, trace::TraceContext};
+use goose::goose::{GooseUser, Scenario, Transaction};
+use std::{fmt::Display, sync::Arc};
 
 /// Truncate from the middle if the string is too long.
 pub fn truncate_middle(s: impl Display, max_len: usize) -> String {
@@ -26,9 +28,41 @@ impl<T: std::fmt::Display> std::fmt::Display for DisplayVec<T> {
 /// This struct is used to store user-specific data that is needed for Goose transactions.
 ///
 /// The advisory ID is randomly selected from the available advisories.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct GooseUserData {
     pub advisory_id: Option<String>,
+    /// Provides the bearer token for each request, unless authentication is disabled
+    pub token_provider: Option<OpenIdTokenProvider>,
+    /// Records the status of each request, when serving live metrics
+    pub live_metrics: Option<Arc<LiveMetrics>>,
+    /// The trace of the transaction currently running
+    pub trace: Option<TraceContext>,
+}
+
+impl GooseUserData {
+    /// Get the data of a user, initializing it if it wasn't set yet.
+    pub fn of(user: &mut GooseUser) -> &mut Self {
+        if user.get_session_data::<Self>().is_none() {
+            user.set_session_data(Self::default());
+        }
+        user.get_session_data_unchecked_mut()
+    }
+}
+
+/// Replace the transactions of a scenario using `wrap`, e.g. to measure them.
+///
+/// The `on_start` and `on_stop` transactions, like the logon, are left alone.
+pub(crate) fn wrap_transactions(
+    mut scenario: Scenario,
+    mut wrap: impl FnMut(Transaction) -> Transaction,
+) -> Scenario {
+    for transaction in &mut scenario.transactions {
+        if !transaction.on_start && !transaction.on_stop {
+            *transaction = wrap(transaction.clone());
+        }
+    }
+
+    scenario
 }
 
 #[cfg(test)]
@@ -44,6 +78,27 @@ mod test {
         assert_eq!("012…5678", truncate_middle("012345678012345678", 8));
     }
 
+    #[test]
+    fn wrap() {
+        let transaction = || Transaction::new(Arc::new(|_| Box::pin(async { Ok(()) })));
+        let scenario = Scenario::new("RestAPIUser")
+            .register_transaction(transaction().set_name("logon").set_on_start())
+            .register_transaction(transaction().set_name("get_sbom"))
+            .register_transaction(transaction().set_name("logoff").set_on_stop());
+
+        let scenario = wrap_transactions(scenario, |transaction| {
+            let name = format!("wrapped {}", transaction.name);
+            transaction.set_name(&name)
+        });
+
+        let names: Vec<_> = scenario
+            .transactions
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, ["logon", "wrapped get_sbom", "logoff"]);
+    }
+
     #[test]
     fn display_vec_single() {
         let v = DisplayVec(vec!["only"]);
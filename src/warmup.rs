@@ -0,0 +1,286 @@
+//! Warm-up periods, running transactions without including them in the results.
+//!
+//! The first minutes of a run measure cold caches, of the database as well as the analysis graphs. The transactions of
+//! the warm-up period are recorded separately, and the results only include the ones of the steady state.
+
+use crate::{stats::TransactionStats, utils::wrap_transactions};
+use goose::goose::{Scenario, Transaction};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// The part of a run used for warming up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Period {
+    /// From the start of the first transaction
+    Duration(Duration),
+    /// The first transactions of the run, of all users together
+    Iterations(usize),
+}
+
+/// The measurements of a transaction, in milliseconds.
+struct Measurements {
+    warmup: TransactionStats,
+    steady: TransactionStats,
+}
+
+impl Measurements {
+    fn new(name: &str) -> Self {
+        let stats = TransactionStats {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        Self {
+            warmup: stats.clone(),
+            steady: stats,
+        }
+    }
+}
+
+/// Splits the transactions of a run into the ones of the warm-up, and the ones of the steady state.
+pub(crate) struct Warmup {
+    period: Option<Period>,
+    /// The start of the first transaction
+    start: OnceLock<Instant>,
+    /// The start of the first transaction of the steady state
+    steady: OnceLock<Instant>,
+    started: AtomicUsize,
+    measurements: Mutex<BTreeMap<String, Measurements>>,
+}
+
+impl Warmup {
+    pub fn new(period: Option<Period>) -> Self {
+        Self {
+            period,
+            start: OnceLock::new(),
+            steady: OnceLock::new(),
+            started: AtomicUsize::new(0),
+            measurements: Default::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.period.is_some()
+    }
+
+    /// Start a transaction, returning whether it is part of the warm-up.
+    pub fn begin(&self, now: Instant) -> bool {
+        let start = *self.start.get_or_init(|| now);
+        let started = self.started.fetch_add(1, Ordering::Relaxed);
+
+        let warmup = match self.period {
+            None => false,
+            Some(Period::Duration(duration)) => now.saturating_duration_since(start) < duration,
+            Some(Period::Iterations(iterations)) => started < iterations,
+        };
+        if !warmup {
+            self.steady.get_or_init(|| now);
+        }
+
+        warmup
+    }
+
+    /// Record a run of a transaction, started using [`Self::begin`].
+    pub async fn record(&self, name: &str, warmup: bool, time: Duration, failed: bool) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut measurements = self.measurements.lock().await;
+        let measurements = measurements
+            .entry(name.to_string())
+            .or_insert_with(|| Measurements::new(name));
+        match warmup {
+            true => measurements.warmup.record(time, failed),
+            false => measurements.steady.record(time, failed),
+        }
+    }
+
+    /// Measure the transactions of a scenario, when there is a warm-up period.
+    pub fn measure(self: &Arc<Self>, scenario: Scenario) -> Scenario {
+        if !self.is_enabled() {
+            return scenario;
+        }
+
+        wrap_transactions(scenario, |transaction| self.measured(transaction))
+    }
+
+    fn measured(self: &Arc<Self>, mut transaction: Transaction) -> Transaction {
+        let function = transaction.function.clone();
+        let name = transaction.name.clone();
+        let warmup = self.clone();
+
+        transaction.function = Arc::new(move |user| {
+            let function = function.clone();
+            let name = name.clone();
+            let warmup = warmup.clone();
+            Box::pin(async move {
+                let start = Instant::now();
+                let warming_up = warmup.begin(start);
+                let result = function(user).await;
+                warmup
+                    .record(&name, warming_up, start.elapsed(), result.is_err())
+                    .await;
+                result
+            })
+        });
+
+        transaction
+    }
+
+    /// The duration of the warm-up, in seconds
+    pub fn warmup_duration(&self) -> usize {
+        match (self.start.get(), self.steady.get()) {
+            (Some(start), Some(steady)) => steady.duration_since(*start).as_secs() as usize,
+            (Some(start), None) => start.elapsed().as_secs() as usize,
+            (None, _) => 0,
+        }
+    }
+
+    /// The duration of the steady state up to now, in seconds
+    pub fn steady_duration(&self) -> usize {
+        self.steady
+            .get()
+            .map(|steady| steady.elapsed().as_secs() as usize)
+            .unwrap_or_default()
+    }
+
+    /// Replace the stats of the measured transactions with the ones of the steady state, returning the ones of the
+    /// warm-up.
+    pub async fn exclude(&self, stats: &mut [TransactionStats]) -> Vec<TransactionStats> {
+        let measurements = self.measurements.lock().await;
+        for stats in stats.iter_mut() {
+            if let Some(measurements) = measurements.get(&stats.name) {
+                stats.clone_from(&measurements.steady);
+            }
+        }
+
+        measurements
+            .values()
+            .map(|measurements| measurements.warmup.clone())
+            .filter(|warmup| warmup.count > 0)
+            .collect()
+    }
+
+    /// Print the transactions of the warm-up next to the ones of the steady state
+    pub async fn print_table(&self) {
+        println!(
+            "Warm-up: {}s, steady state: {}s",
+            self.warmup_duration(),
+            self.steady_duration()
+        );
+
+        let measurements = self.measurements.lock().await;
+        let width = measurements
+            .keys()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max("TRANSACTION".len());
+
+        println!(
+            "\n{:<width$}  {:>7}  {:>11}  {:>11}  {:>7}  {:>11}  {:>11}",
+            "TRANSACTION",
+            "WARM-UP",
+            "WARM-UP P50",
+            "WARM-UP P95",
+            "STEADY",
+            "STEADY P50",
+            "STEADY P95"
+        );
+        for (name, measurements) in measurements.iter() {
+            println!(
+                "{:<width$}  {:>7}  {:>9}ms  {:>9}ms  {:>7}  {:>9}ms  {:>9}ms",
+                name,
+                measurements.warmup.count,
+                measurements.warmup.percentile(0.5),
+                measurements.warmup.percentile(0.95),
+                measurements.steady.count,
+                measurements.steady.percentile(0.5),
+                measurements.steady.percentile(0.95),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn duration() {
+        let warmup = Warmup::new(Some(Period::Duration(Duration::from_secs(60))));
+        let now = Instant::now();
+
+        assert!(warmup.begin(now));
+        assert!(warmup.begin(now + Duration::from_secs(59)));
+        assert!(!warmup.begin(now + Duration::from_secs(60)));
+        assert_eq!(warmup.warmup_duration(), 60);
+    }
+
+    #[test]
+    fn iterations() {
+        let warmup = Warmup::new(Some(Period::Iterations(2)));
+        let now = Instant::now();
+
+        assert!(warmup.begin(now));
+        assert!(warmup.begin(now));
+        assert!(!warmup.begin(now));
+        assert!(!warmup.begin(now));
+    }
+
+    #[test]
+    fn disabled() {
+        let warmup = Warmup::new(None);
+        assert!(!warmup.begin(Instant::now()));
+    }
+
+    #[test]
+    fn exclude() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("must build runtime");
+        let warmup = Warmup::new(Some(Period::Iterations(1)));
+        let mut stats = [
+            TransactionStats {
+                name: "get_sbom".into(),
+                times: [(500, 1), (20, 1), (30, 1)].into_iter().collect(),
+                total_time: 550,
+                count: 3,
+                failed: 1,
+            },
+            TransactionStats {
+                name: "unmeasured".into(),
+                count: 1,
+                ..Default::default()
+            },
+        ];
+
+        let excluded = runtime.block_on(async {
+            warmup
+                .record("get_sbom", true, Duration::from_millis(500), false)
+                .await;
+            warmup
+                .record("get_sbom", false, Duration::from_millis(20), false)
+                .await;
+            warmup
+                .record("get_sbom", false, Duration::from_millis(30), true)
+                .await;
+            warmup.exclude(&mut stats).await
+        });
+
+        assert_eq!(stats[0].count, 2);
+        assert_eq!(stats[0].failed, 1);
+        assert_eq!(stats[0].total_time, 50);
+        assert_eq!(stats[1].count, 1);
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[0].name, "get_sbom");
+        assert_eq!(excluded[0].total_time, 500);
+    }
+}
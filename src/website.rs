@@ -1,37 +1,38 @@
+use crate::restapi::request;
 use goose::goose::{GooseUser, TransactionResult};
 
 pub async fn website_index(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("").await?;
+    let _response = request::get(user, "").await?;
 
     Ok(())
 }
 
 pub async fn website_openapi(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("/openapi").await?;
+    let _response = request::get(user, "/openapi").await?;
 
     Ok(())
 }
 
 pub async fn website_sboms(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("/sboms").await?;
+    let _response = request::get(user, "/sboms").await?;
 
     Ok(())
 }
 
 pub async fn website_packages(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("/packages").await?;
+    let _response = request::get(user, "/packages").await?;
 
     Ok(())
 }
 
 pub async fn website_advisories(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("/advisories").await?;
+    let _response = request::get(user, "/advisories").await?;
 
     Ok(())
 }
 
 pub async fn website_importers(user: &mut GooseUser) -> TransactionResult {
-    let _response = user.get("/importers").await?;
+    let _response = request::get(user, "/importers").await?;
 
     Ok(())
 }
@@ -0,0 +1,275 @@
+use crate::{
+    assertion::Assertions,
+    generator::{advisory::AdvisoryConfig, sbom::SbomConfig},
+    restapi::{analysis::GraphWalkConfig, journey::JourneyConfig},
+};
+use anyhow::Context;
+use std::io::BufReader;
+
+/// The workload shipped with the load test, used when no workload file is provided.
+const DEFAULT_WORKLOAD: &str = include_str!("../workloads/default.json5");
+
+/// The user populations of a load test run.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Workload {
+    pub users: Vec<User>,
+}
+
+/// A single user population, registered as a goose scenario.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct User {
+    /// The name of the goose scenario
+    pub name: String,
+
+    /// The relative weight of this user compared to the others
+    #[serde(default = "default_weight")]
+    pub weight: usize,
+
+    /// The wait time between transactions, overriding the one from the command line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_time: Option<WaitTime>,
+
+    /// Start transactions at a constant rate (per second, shared by all users of the population) instead of waiting
+    /// between them
+    #[serde(
+        default,
+        deserialize_with = "rate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rate: Option<f64>,
+
+    /// Requests to issue, as a path with an optional (pre-encoded) query string.
+    ///
+    /// Each entry becomes a `GET` transaction named by its URL.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub list: Vec<ListRequest>,
+
+    /// The SBOMs uploaded by the `IngestUser`, defaults to the `generate-sbom` defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<SbomConfig>,
+
+    /// The advisories uploaded by the `AdvisoryIngestUser`, defaults to the `generate-advisory` defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advisory: Option<AdvisoryConfig>,
+
+    /// The searches starting the journeys of the `JourneyUser`, defaults to the most recent entities
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journey: Option<JourneyConfig>,
+
+    /// The walks through the analysis graph of the `GraphWalkUser`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_walk: Option<GraphWalkConfig>,
+}
+
+/// A list request, either a plain URL or a URL with assertions on its response.
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub(crate) enum ListRequest {
+    Url(String),
+    Asserted { url: String, assert: Assertions },
+}
+
+impl ListRequest {
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Url(url) | Self::Asserted { url, .. } => url,
+        }
+    }
+
+    /// The assertions of the request, plain URLs are expected to return JSON.
+    pub fn assertions(&self) -> Assertions {
+        match self {
+            Self::Url(_) => Assertions::json(),
+            Self::Asserted { assert, .. } => assert.clone(),
+        }
+    }
+}
+
+/// Wait time between two transactions, in seconds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct WaitTime {
+    pub from: u64,
+    pub to: u64,
+}
+
+fn default_weight() -> usize {
+    1
+}
+
+/// Parse a rate, which must be positive.
+fn rate<'de, D>(d: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match <Option<f64> as serde::Deserialize>::deserialize(d)? {
+        Some(rate) if !(rate.is_finite() && rate > 0.0) => Err(serde::de::Error::custom(format!(
+            "rate must be positive: {rate}"
+        ))),
+        rate => Ok(rate),
+    }
+}
+
+impl Workload {
+    /// Load a workload file, or use the default workload
+    pub fn load(workload_file: Option<&str>) -> anyhow::Result<Self> {
+        match workload_file {
+            Some(workload_file) => serde_json5::from_reader(BufReader::new(
+                std::fs::File::open(workload_file)
+                    .with_context(|| format!("opening workload file: {workload_file}"))?,
+            ))
+            .context("reading workload file"),
+            None => serde_json5::from_str(DEFAULT_WORKLOAD).context("reading default workload"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::advisory::AdvisoryFormat;
+
+    #[test]
+    fn default() {
+        let workload = Workload::load(None).expect("Must be ok");
+        let names: Vec<_> = workload.users.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "WebsiteUser",
+                "RestAPIUser",
+                "RestAPIUserSlow",
+                "AnalysisUser",
+                "RestAPIUserDelete",
+                "RestSBOMLabelUser",
+                "RestAdvisoryLableUser",
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let workload: Workload =
+            serde_json5::from_str(r#"{"users": [{"name": "Foo"}]}"#).expect("Must be ok");
+        assert_eq!(
+            workload.users,
+            [User {
+                name: "Foo".into(),
+                weight: 1,
+                wait_time: None,
+                rate: None,
+                list: vec![],
+                sbom: None,
+                advisory: None,
+                journey: None,
+                graph_walk: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn full() {
+        let workload: Workload = serde_json5::from_str(
+            r#"{
+  users: [
+    {
+      name: "Foo",
+      weight: 3,
+      wait_time: { from: 1, to: 2 },
+      list: ["/api/v3/sbom", "/api/v3/sbom?q=name~redhat"],
+    },
+  ],
+}"#,
+        )
+        .expect("Must be ok");
+        assert_eq!(workload.users[0].weight, 3);
+        assert_eq!(
+            workload.users[0].wait_time,
+            Some(WaitTime { from: 1, to: 2 })
+        );
+        assert_eq!(workload.users[0].list.len(), 2);
+    }
+
+    #[test]
+    fn list_assertions() {
+        let workload: Workload = serde_json5::from_str(
+            r#"{
+  users: [
+    {
+      name: "Foo",
+      list: [
+        "/api/v3/sbom",
+        { url: "/api/v3/advisory", assert: { min_items: 1, json: ["/total > 0"] } },
+      ],
+    },
+  ],
+}"#,
+        )
+        .expect("Must be ok");
+        let list = &workload.users[0].list;
+        assert_eq!(list[0].url(), "/api/v3/sbom");
+        assert_eq!(list[0].assertions(), Assertions::json());
+        assert_eq!(list[1].url(), "/api/v3/advisory");
+        assert_eq!(list[1].assertions().min_items, Some(1));
+        assert_eq!(list[1].assertions().json.len(), 1);
+    }
+
+    #[test]
+    fn unknown_list_field() {
+        serde_json5::from_str::<Workload>(
+            r#"{"users": [{"name": "Foo", "list": [{"url": "/api/v3/sbom", "asert": {}}]}]}"#,
+        )
+        .expect_err("Must be an error");
+    }
+
+    #[test]
+    fn sbom() {
+        let workload: Workload = serde_json5::from_str(
+            r#"{"users": [{"name": "IngestUser", "sbom": {"format": "cyclonedx", "depth": 5}}]}"#,
+        )
+        .expect("Must be ok");
+        let sbom = workload.users[0]
+            .sbom
+            .clone()
+            .expect("Must have an SBOM config");
+        assert_eq!(sbom.depth, 5);
+        assert_eq!(sbom.packages, SbomConfig::default().packages);
+    }
+
+    #[test]
+    fn advisory() {
+        let workload: Workload = serde_json5::from_str(
+            r#"{"users": [{"name": "AdvisoryIngestUser", "advisory": {"format": "osv", "affected": 50}}]}"#,
+        )
+        .expect("Must be ok");
+        let advisory = workload.users[0]
+            .advisory
+            .clone()
+            .expect("Must have an advisory config");
+        assert_eq!(advisory.format, AdvisoryFormat::Osv);
+        assert_eq!(advisory.affected, 50);
+    }
+
+    #[test]
+    fn rate() {
+        let workload: Workload =
+            serde_json5::from_str(r#"{"users": [{"name": "Foo", "rate": 0.5}]}"#)
+                .expect("Must be ok");
+        assert_eq!(workload.users[0].rate, Some(0.5));
+
+        for rate in ["0", "-1"] {
+            serde_json5::from_str::<Workload>(&format!(
+                r#"{{"users": [{{"name": "Foo", "rate": {rate}}}]}}"#
+            ))
+            .expect_err("Must be an error");
+        }
+    }
+
+    #[test]
+    fn unknown_field() {
+        serde_json5::from_str::<Workload>(r#"{"users": [{"name": "Foo", "wieght": 3}]}"#)
+            .expect_err("Must be an error");
+    }
+}